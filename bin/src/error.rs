@@ -19,6 +19,9 @@ impl IntoResponse for Exception {
             ApplicationError::DeserializationError(err) => {
                 (StatusCode::BAD_REQUEST, err.to_string())
             }
+            ApplicationError::SerializationError(err) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            }
 
             ApplicationError::InvalidURL => (
                 StatusCode::BAD_REQUEST,
@@ -42,6 +45,35 @@ impl IntoResponse for Exception {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 ApplicationError::ParsingError.to_string(),
             ),
+            command @ ApplicationError::IdempotencyConflict
+            | command @ ApplicationError::ConcurrencyConflict
+            | command @ ApplicationError::EntityAlreadyExists(_) => {
+                (StatusCode::CONFLICT, command.to_string())
+            }
+            err @ ApplicationError::InvalidCredentials
+            | err @ ApplicationError::InvalidToken
+            | err @ ApplicationError::TokenReused => (StatusCode::UNAUTHORIZED, err.to_string()),
+            err @ ApplicationError::Forbidden | err @ ApplicationError::AccountNotVerified => {
+                (StatusCode::FORBIDDEN, err.to_string())
+            }
+            err @ ApplicationError::TooManyRequests => {
+                (StatusCode::TOO_MANY_REQUESTS, err.to_string())
+            }
+            // * Locked account is reported as invalid credentials, so that it doesn't tell the account exists.
+            ApplicationError::AccountLocked => (
+                StatusCode::UNAUTHORIZED,
+                ApplicationError::InvalidCredentials.to_string(),
+            ),
+            err @ ApplicationError::HandlerPanicked(_)
+            | err @ ApplicationError::InvalidConfig(_)
+            | err @ ApplicationError::SchemaDrift(_)
+            | err @ ApplicationError::PasswordHashingError(_)
+            | err @ ApplicationError::MailDeliveryError(_)
+            | err @ ApplicationError::SecretEncryptionError(_)
+            | err @ ApplicationError::EventChannelFull
+            | err @ ApplicationError::EventChannelClosed => {
+                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            }
         };
         let body = Json(json!({ "error": error_message }));
         (status, body).into_response()
@@ -61,6 +93,7 @@ impl IntoResponse for WebResponse<ServiceResponse> {
                 }
             }
             ServiceResponse::String(s) => s.into_response(),
+            ServiceResponse::Json(value) => Json(value).into_response(),
             ServiceResponse::Empty(_) => ().into_response(),
         }
     }
@@ -0,0 +1,119 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{
+        header::{AUTHORIZATION, USER_AGENT},
+        request::Parts,
+        StatusCode,
+    },
+};
+use library::{
+    adapters::jwt::AccessToken, domain::auth::entity::AccountRole, utils::ApplicationError,
+};
+use uuid::Uuid;
+
+use crate::error::Exception;
+
+/// Value of `Idempotency-Key` header, if any.
+/// Client retrying the same request with the same key gets the response to the first one.
+pub struct IdempotencyKey(pub Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get("Idempotency-Key") {
+            None => Ok(Self(None)),
+            Some(value) => match value.to_str() {
+                Ok(key) if !key.is_empty() => Ok(Self(Some(key.to_string()))),
+                _ => Err((StatusCode::BAD_REQUEST, "Invalid Idempotency-Key")),
+            },
+        }
+    }
+}
+
+/// Account authenticated by the bearer token in `Authorization` header.
+/// Request without valid token, or with token whose session has been revoked, is rejected as `InvalidToken`.
+pub struct CurrentAccount {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub role: AccountRole,
+    pub verified: bool,
+}
+
+impl CurrentAccount {
+    pub fn is_moderator(&self) -> bool {
+        self.role == AccountRole::Moderator
+    }
+
+    /// Reject account whose email has not been verified as `AccountNotVerified`.
+    pub fn ensure_verified(&self) -> Result<(), ApplicationError> {
+        if !self.verified {
+            return Err(ApplicationError::AccountNotVerified);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentAccount
+where
+    S: Send + Sync,
+{
+    type Rejection = Exception;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(ApplicationError::InvalidToken)?;
+        let claims = AccessToken::verify_active(token.trim()).await?;
+        let id = claims
+            .sub
+            .parse()
+            .map_err(|_| ApplicationError::InvalidToken)?;
+        Ok(Self {
+            id,
+            session_id: claims.sid,
+            role: claims.role,
+            verified: claims.verified,
+        })
+    }
+}
+
+/// Client the request comes from, which is recorded on login for audit.
+/// Address is that of the peer connected, as forwarding headers can be forged by anyone.
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            ip: parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string()),
+            user_agent: parts
+                .headers
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+        })
+    }
+}
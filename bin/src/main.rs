@@ -1,19 +1,33 @@
 mod error;
+mod extractors;
+mod migrate;
 mod routes;
 
-use std::env;
+use std::net::SocketAddr;
 
 use axum::{
     http::{HeaderValue, Method},
     Router,
 };
-use routes::board_routers;
+use routes::{auth_routers, board_routers};
 
-use library::{bootstrap::Boostrap, domain::board::commands::*};
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use library::{
+    adapters::migration::Migration,
+    bootstrap::{connection_pool, Boostrap},
+    config::Config,
+    domain::{auth::commands::*, board::commands::*},
+    services::scheduler::Scheduler,
+};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use utoipa::OpenApi;
+use utoipa::{
+    openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
 use utoipa_swagger_ui::SwaggerUi;
 
 #[tokio::main]
@@ -21,6 +35,39 @@ async fn main() {
     println!("Environment Variable Is Being Set...");
     dotenv::dotenv().expect("Unable to load environment variable!");
 
+    // ! Configuration
+    let config = Config::load().unwrap_or_else(|err| {
+        eprintln!("Configuration Is Invalid! {}", err);
+        std::process::exit(1)
+    });
+    let allow_origins = config
+        .server
+        .allow_origins
+        .iter()
+        .map(|origin| origin.parse::<HeaderValue>())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|err| {
+            eprintln!("Configuration Is Invalid! server.allow_origins: {}", err);
+            std::process::exit(1)
+        });
+    Boostrap::configure(config.clone()).expect("Configuration set failed!");
+
+    // ! Migration
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        if let Err(err) = migrate::migrate(&args[1..]).await {
+            eprintln!("Migration Failed! {}", err);
+            std::process::exit(1)
+        }
+        return;
+    }
+    if let Err(err) =
+        Migration::ensure(connection_pool().await, config.database.migration_mode).await
+    {
+        eprintln!("Schema Is Not Up To Date! {}", err);
+        std::process::exit(1)
+    }
+
     // ! OpenAPI
     #[derive(OpenApi)]
     #[openapi(
@@ -28,28 +75,78 @@ async fn main() {
         routes::create_board,
         routes::edit_board,
         routes::add_comment,
-        routes::edit_comment
+        routes::edit_comment,
+        routes::register_account,
+        routes::login,
+        routes::verify_mfa,
+        routes::refresh_token,
+        routes::verify_email,
+        routes::request_verification,
+        routes::request_password_reset,
+        routes::reset_password,
+        routes::change_password,
+        routes::unblock_account,
+        routes::enable_totp,
+        routes::confirm_totp,
+        routes::disable_totp,
+        routes::list_sessions,
+        routes::revoke_session,
+        routes::revoke_all_sessions
     ),
     components(
         schemas(
             CreateBoard,
             EditBoard,
             AddComment,
-            EditComment)
+            EditComment,
+            RegisterAccount,
+            Login,
+            RefreshToken,
+            VerifyEmail,
+            RequestVerification,
+            RequestPasswordReset,
+            ResetPassword,
+            ChangePassword,
+            UnblockAccount,
+            VerifyMfa,
+            ConfirmTotp,
+            DisableTotp,
+            IssuedToken,
+            MfaChallenge,
+            TotpEnrollment,
+            SessionSummary)
     ),
     tags(
         (name= "Rustiful Backend", description="This is for swagger integration")
-    )
+    ),
+    modifiers(&SecurityAddon)
     )]
     pub struct ApiDoc;
 
+    struct SecurityAddon;
+    impl Modify for SecurityAddon {
+        fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+            if let Some(components) = openapi.components.as_mut() {
+                components.add_security_scheme(
+                    "bearer",
+                    SecurityScheme::Http(
+                        HttpBuilder::new()
+                            .scheme(HttpAuthScheme::Bearer)
+                            .bearer_format("JWT")
+                            .build(),
+                    ),
+                )
+            }
+        }
+    }
+
     // ! Tracing
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
                 // axum logs rejections from built-in extractors with the `axum::rejection`
                 // target, at `TRACE` level. `axum::rejection=trace` enables showing those events
-                "tracing=debug,tower_http=debug,axum::rejection=trace".into()
+                "tracing=debug,tower_http=debug,axum::rejection=trace,library=info".into()
             }),
         )
         .with(tracing_subscriber::fmt::layer())
@@ -60,18 +157,23 @@ async fn main() {
 
     let bus = Boostrap::message_bus().await;
 
-    let app = Router::new()
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
+    // ! Scheduled Commands
+    if config.features.scheduler {
+        tokio::spawn(Scheduler::run(bus.clone(), config.relay.interval()));
+    }
+
+    let mut app = Router::new()
         .nest("/boards", board_routers())
+        .nest("/auth", auth_routers());
+    if config.features.swagger {
+        app = app
+            .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()));
+    }
+    let app = app
         .with_state(bus.clone())
         .layer(
             CorsLayer::new()
-                .allow_origin(
-                    env::var("ALLOW_ORIGINS")
-                        .expect("CORS origin set failed!")
-                        .parse::<HeaderValue>()
-                        .unwrap(),
-                )
+                .allow_origin(AllowOrigin::list(allow_origins))
                 .allow_methods([
                     Method::GET,
                     Method::POST,
@@ -83,13 +185,8 @@ async fn main() {
         .layer(TraceLayer::new_for_http());
 
     println!("Binding...");
-    axum::Server::bind(
-        &env::var("DOMAIN")
-            .expect("failed to bind!")
-            .parse()
-            .expect("failed to parse!"),
-    )
-    .serve(app.into_make_service())
-    .await
-    .unwrap();
+    axum::Server::bind(&config.server.domain.parse().expect("failed to parse!"))
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
@@ -0,0 +1,57 @@
+use library::{
+    adapters::migration::{Migration, MigrationState},
+    bootstrap::connection_pool,
+    utils::ApplicationResult,
+};
+
+const USAGE: &str = "Usage: bin migrate <up|down|status> [--dry-run]";
+
+/// `migrate` subcommand.
+/// `up` applies pending migrations, `down` reverts the latest one applied and `status` lists every migration.
+pub async fn migrate(args: &[String]) -> ApplicationResult<()> {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let pool = connection_pool().await;
+
+    match args.first().map(String::as_str) {
+        Some("up") => {
+            let pending = Migration::up(pool, dry_run).await?;
+            if pending.is_empty() {
+                println!("Nothing To Migrate!");
+            }
+            for version in pending {
+                println!(
+                    "{} {}",
+                    if dry_run { "Would Apply" } else { "Applied" },
+                    version
+                );
+            }
+        }
+        Some("down") => match Migration::down(pool, dry_run).await? {
+            Some(version) => {
+                println!(
+                    "{} {}",
+                    if dry_run { "Would Revert" } else { "Reverted" },
+                    version
+                )
+            }
+            None => println!("Nothing To Revert!"),
+        },
+        Some("status") => {
+            for status in Migration::status(pool).await? {
+                let state = match status.state {
+                    MigrationState::Applied => "applied",
+                    MigrationState::Pending => "pending",
+                    MigrationState::ChecksumMismatch => "checksum mismatch",
+                    MigrationState::Dirty => "dirty",
+                    MigrationState::Missing => "missing",
+                };
+                println!("{}\t{}\t{}", status.version, state, status.description);
+            }
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2)
+        }
+    }
+    Ok(())
+}
@@ -1,51 +1,386 @@
 use std::sync::Arc;
 
-use axum::routing::post;
+use axum::routing::{delete, get, post, put};
 use axum::Router;
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
 use library::domain::commands::ServiceResponse;
 
 use crate::error::{Exception, WebResponse};
+use crate::extractors::{ClientInfo, CurrentAccount, IdempotencyKey};
+use library::domain::auth::commands::*;
 use library::domain::board::commands::*;
 use library::services::messagebus::MessageBus;
+use uuid::Uuid;
 
-#[utoipa::path( post,  path = "/boards", request_body=CreateBoard)]
+#[utoipa::path(
+    post,
+    path = "/boards",
+    request_body = CreateBoard,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to replay the response to a retried request")),
+    security(("bearer" = []))
+)]
 #[axum_macros::debug_handler]
 pub async fn create_board(
     State(bus): State<Arc<MessageBus>>,
-    Json(cmd): Json<CreateBoard>,
+    account: CurrentAccount,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(mut cmd): Json<CreateBoard>,
 ) -> Result<WebResponse<ServiceResponse>, Exception> {
-    let res = bus.handle(cmd).await.map_err(Exception)?;
+    account.ensure_verified()?;
+    cmd.author = account.id;
+    let res = bus
+        .handle_idempotent(cmd, idempotency_key)
+        .await
+        .map_err(Exception)?;
 
     Ok(WebResponse(res))
 }
 
-#[utoipa::path(patch, path = "/boards",request_body=EditBoard)]
+#[utoipa::path(
+    patch,
+    path = "/boards",
+    request_body = EditBoard,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to replay the response to a retried request")),
+    security(("bearer" = []))
+)]
 #[axum_macros::debug_handler]
 pub async fn edit_board(
     State(bus): State<Arc<MessageBus>>,
-    Json(cmd): Json<EditBoard>,
+    account: CurrentAccount,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(mut cmd): Json<EditBoard>,
 ) -> Result<WebResponse<ServiceResponse>, Exception> {
-    let res = bus.handle(cmd).await.map_err(Exception)?;
+    cmd.editor = account.id;
+    cmd.moderator = account.is_moderator();
+    let res = bus
+        .handle_idempotent(cmd, idempotency_key)
+        .await
+        .map_err(Exception)?;
 
     Ok(WebResponse(res))
 }
 
-#[utoipa::path(post, path = "/boards/comments",request_body=AddComment)]
+#[utoipa::path(
+    post,
+    path = "/boards/comments",
+    request_body = AddComment,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to replay the response to a retried request")),
+    security(("bearer" = []))
+)]
 pub async fn add_comment(
     State(bus): State<Arc<MessageBus>>,
-    Json(cmd): Json<AddComment>,
+    account: CurrentAccount,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(mut cmd): Json<AddComment>,
 ) -> Result<WebResponse<ServiceResponse>, Exception> {
-    let res = bus.handle(cmd).await.map_err(Exception)?;
+    cmd.author = account.id;
+    let res = bus
+        .handle_idempotent(cmd, idempotency_key)
+        .await
+        .map_err(Exception)?;
 
     Ok(WebResponse(res))
 }
 
-#[utoipa::path(patch, path = "/boards/comments",request_body=EditComment)]
+#[utoipa::path(
+    patch,
+    path = "/boards/comments",
+    request_body = EditComment,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to replay the response to a retried request")),
+    security(("bearer" = []))
+)]
 pub async fn edit_comment(
     State(bus): State<Arc<MessageBus>>,
-    Json(cmd): Json<EditComment>,
+    account: CurrentAccount,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(mut cmd): Json<EditComment>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    cmd.editor = account.id;
+    cmd.moderator = account.is_moderator();
+    let res = bus
+        .handle_idempotent(cmd, idempotency_key)
+        .await
+        .map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/accounts",
+    request_body = RegisterAccount,
+    params(("Idempotency-Key" = Option<String>, Header, description = "Key to replay the response to a retried request"))
+)]
+pub async fn register_account(
+    State(bus): State<Arc<MessageBus>>,
+    IdempotencyKey(idempotency_key): IdempotencyKey,
+    Json(cmd): Json<RegisterAccount>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let res = bus
+        .handle_idempotent(cmd, idempotency_key)
+        .await
+        .map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login",
+    request_body = Login,
+    responses(
+        (status = 200, description = "Tokens issued, or MfaChallenge for account with second factor", body = IssuedToken),
+        (status = 401, description = "Invalid credentials, or account locked after too many failures")
+    )
+)]
+pub async fn login(
+    State(bus): State<Arc<MessageBus>>,
+    client: ClientInfo,
+    Json(mut cmd): Json<Login>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    cmd.ip = client.ip;
+    cmd.user_agent = client.user_agent;
+    // * Not idempotent, so that tokens issued are never stored to be replayed.
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/login/mfa",
+    request_body = VerifyMfa,
+    responses((status = 200, body = IssuedToken), (status = 401, description = "Invalid credentials, or account locked after too many failures"))
+)]
+pub async fn verify_mfa(
+    State(bus): State<Arc<MessageBus>>,
+    client: ClientInfo,
+    Json(mut cmd): Json<VerifyMfa>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    cmd.ip = client.ip;
+    cmd.user_agent = client.user_agent;
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    request_body = RefreshToken,
+    responses((status = 200, body = IssuedToken))
+)]
+pub async fn refresh_token(
+    State(bus): State<Arc<MessageBus>>,
+    Json(cmd): Json<RefreshToken>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email",
+    request_body = VerifyEmail
+)]
+pub async fn verify_email(
+    State(bus): State<Arc<MessageBus>>,
+    Json(cmd): Json<VerifyEmail>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/verify-email/resend",
+    request_body = RequestVerification,
+    responses((status = 200))
+)]
+pub async fn request_verification(
+    State(bus): State<Arc<MessageBus>>,
+    Json(cmd): Json<RequestVerification>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset",
+    request_body = RequestPasswordReset,
+    responses((status = 200))
+)]
+pub async fn request_password_reset(
+    State(bus): State<Arc<MessageBus>>,
+    Json(cmd): Json<RequestPasswordReset>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/password-reset/confirm",
+    request_body = ResetPassword
+)]
+pub async fn reset_password(
+    State(bus): State<Arc<MessageBus>>,
+    Json(cmd): Json<ResetPassword>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    put,
+    path = "/auth/password",
+    request_body = ChangePassword,
+    security(("bearer" = []))
+)]
+pub async fn change_password(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+    Json(mut cmd): Json<ChangePassword>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    cmd.account_id = account.id.to_string();
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/accounts/{id}/unblock",
+    params(("id" = String, Path, description = "Account to unblock")),
+    security(("bearer" = []))
+)]
+pub async fn unblock_account(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+    Path(id): Path<String>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let cmd = UnblockAccount {
+        account_id: id,
+        moderator: account.is_moderator(),
+    };
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/totp",
+    responses((status = 200, body = TotpEnrollment), (status = 409, description = "TOTP already enabled")),
+    security(("bearer" = []))
+)]
+pub async fn enable_totp(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let cmd = EnableTotp {
+        account_id: account.id.to_string(),
+    };
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/totp/confirm",
+    request_body = ConfirmTotp,
+    security(("bearer" = []))
+)]
+pub async fn confirm_totp(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+    Json(mut cmd): Json<ConfirmTotp>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    cmd.account_id = account.id.to_string();
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/totp/disable",
+    request_body = DisableTotp,
+    security(("bearer" = []))
+)]
+pub async fn disable_totp(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+    Json(mut cmd): Json<DisableTotp>,
 ) -> Result<WebResponse<ServiceResponse>, Exception> {
+    cmd.account_id = account.id.to_string();
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/sessions",
+    responses((status = 200, body = [SessionSummary])),
+    security(("bearer" = []))
+)]
+pub async fn list_sessions(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let cmd = ListSessions {
+        account_id: account.id.to_string(),
+        current_session_id: Some(account.session_id),
+    };
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions/{id}",
+    params(("id" = Uuid, Path, description = "Session to revoke")),
+    security(("bearer" = []))
+)]
+pub async fn revoke_session(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+    Path(id): Path<Uuid>,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let cmd = RevokeSession {
+        account_id: account.id.to_string(),
+        session_id: id,
+    };
+    let res = bus.handle(cmd).await.map_err(Exception)?;
+
+    Ok(WebResponse(res))
+}
+
+/// Log out everywhere, including the session the request is made with.
+#[utoipa::path(
+    delete,
+    path = "/auth/sessions",
+    security(("bearer" = []))
+)]
+pub async fn revoke_all_sessions(
+    State(bus): State<Arc<MessageBus>>,
+    account: CurrentAccount,
+) -> Result<WebResponse<ServiceResponse>, Exception> {
+    let cmd = RevokeAllSessions {
+        account_id: account.id.to_string(),
+    };
     let res = bus.handle(cmd).await.map_err(Exception)?;
 
     Ok(WebResponse(res))
@@ -56,3 +391,22 @@ pub fn board_routers() -> Router<Arc<MessageBus>> {
         .route("/", post(create_board).patch(edit_board))
         .route("/comments", post(add_comment).patch(edit_comment))
 }
+
+pub fn auth_routers() -> Router<Arc<MessageBus>> {
+    Router::new()
+        .route("/accounts", post(register_account))
+        .route("/accounts/:id/unblock", post(unblock_account))
+        .route("/login", post(login))
+        .route("/login/mfa", post(verify_mfa))
+        .route("/refresh", post(refresh_token))
+        .route("/verify-email", post(verify_email))
+        .route("/verify-email/resend", post(request_verification))
+        .route("/password-reset", post(request_password_reset))
+        .route("/password-reset/confirm", post(reset_password))
+        .route("/password", put(change_password))
+        .route("/totp", post(enable_totp))
+        .route("/totp/confirm", post(confirm_totp))
+        .route("/totp/disable", post(disable_totp))
+        .route("/sessions", get(list_sessions).delete(revoke_all_sessions))
+        .route("/sessions/:id", delete(revoke_session))
+}
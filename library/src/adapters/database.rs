@@ -1,60 +1,292 @@
-use crate::bootstrap::connection_pool;
+use crate::bootstrap::{config, connection_pool, replica_pools};
 use crate::utils::ApplicationError;
 use crate::{domain::Message, utils::ApplicationResult};
 
-use std::{mem, sync::Arc};
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
+use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPool, Postgres, Transaction};
 
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{mpsc::channel, RwLock};
 
 pub type AtomicContextManager = Arc<RwLock<ContextManager>>;
+pub(crate) type PendingEvents = Arc<RwLock<VecDeque<Box<dyn Message>>>>;
+
+/// Transaction of the outermost unit of work in progress on a context,
+/// which units of work begun on the same context meanwhile join as savepoints.
+#[derive(Clone)]
+pub(crate) struct OngoingTransaction {
+    pub(crate) executor: Arc<RwLock<Executor>>,
+    pub(crate) pending_events: PendingEvents,
+}
+
+/// What to do with an internally notifiable event when the event channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackpressurePolicy {
+    /// Wait until the bus, which drains the channel while handlers run, makes room for the event.
+    Block,
+    #[default]
+    Error,
+    /// Leave the event as outbox so it is processed later on.
+    /// Event that `Outbox::convert_event` cannot convert is reported as `EventChannelFull` instead.
+    Spill,
+}
+
+impl std::str::FromStr for BackpressurePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Self::Block),
+            "error" => Ok(Self::Error),
+            "spill" => Ok(Self::Spill),
+            _ => Err(format!("expected block, error or spill but got {:?}", s)),
+        }
+    }
+}
+
+/// Capacity must be greater than 0, as checked by `Config::validate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EventChannelConfig {
+    pub capacity: usize,
+    pub policy: BackpressurePolicy,
+}
+
+impl Default for EventChannelConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 20,
+            policy: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl IsolationLevel {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::ReadCommitted => "READ COMMITTED",
+            Self::RepeatableRead => "REPEATABLE READ",
+            Self::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// Characteristics of the transaction begun by `Executor`, applied with `SET TRANSACTION` right after `BEGIN`.
+/// Anything left unset falls back to the database default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TransactionOptions {
+    pub isolation_level: Option<IsolationLevel>,
+    pub read_only: bool,
+    /// Only takes effect on `SERIALIZABLE READ ONLY` transaction, which then never fails on serialization.
+    pub deferrable: bool,
+    /// Falls back to `database.statement_timeout_millis` in configuration when unset.
+    pub statement_timeout: Option<Duration>,
+}
+
+impl TransactionOptions {
+    /// Options for query handlers which must not write anything.
+    pub fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn serializable() -> Self {
+        Self {
+            isolation_level: Some(IsolationLevel::Serializable),
+            ..Default::default()
+        }
+    }
+
+    fn statements(&self) -> Vec<String> {
+        let mut modes = vec![];
+        if let Some(isolation_level) = self.isolation_level {
+            modes.push(format!("ISOLATION LEVEL {}", isolation_level.as_sql()));
+        }
+        if self.read_only {
+            modes.push("READ ONLY".into());
+        }
+        if self.deferrable {
+            modes.push("DEFERRABLE".into());
+        }
+
+        let mut statements = vec![];
+        if !modes.is_empty() {
+            statements.push(format!("SET TRANSACTION {}", modes.join(", ")));
+        }
+        if let Some(timeout) = self.statement_timeout {
+            statements.push(format!(
+                "SET LOCAL statement_timeout = {}",
+                timeout.as_millis()
+            ));
+        }
+        statements
+    }
+}
+
+/// Where reads outside transaction go. Reads within transaction always go to the primary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadPreference {
+    #[default]
+    Replica,
+    /// Read your own writes, which replica may not have caught up with yet.
+    Primary,
+}
+
+// * Replicas are taken in turn across executors.
+static REPLICA_SEQ: AtomicUsize = AtomicUsize::new(0);
 
 /// Task Local Context Manager
 /// This is called for every time Messagebus.handle is invoked within which it manages events raised in service.
 /// It spawns out Executor that manages transaction.
 pub struct ContextManager {
     pub pool: &'static PgPool,
+    pub replicas: &'static [PgPool],
 
     pub sender: Sender<Box<dyn Message>>,
+    pub backpressure_policy: BackpressurePolicy,
+
+    /// Options for transactions begun by executors spawned out of this context.
+    pub transaction_options: TransactionOptions,
+
+    /// Set to `ReadPreference::Primary` for the request that has to read what it has just written.
+    pub read_preference: ReadPreference,
+
+    pub(crate) ongoing_transaction: Option<OngoingTransaction>,
 }
 
 impl ContextManager {
     /// Creation of context manager returns context manager AND event receiver
     pub async fn new() -> (Arc<RwLock<Self>>, Receiver<Box<dyn Message>>) {
+        Self::with_channel_config(config().event_channel).await
+    }
+    pub async fn with_channel_config(
+        config: EventChannelConfig,
+    ) -> (Arc<RwLock<Self>>, Receiver<Box<dyn Message>>) {
         let pool = connection_pool().await;
-        let (sender, receiver) = channel(20);
-        (Arc::new(RwLock::new(Self { pool, sender })), receiver)
+        let replicas = replica_pools().await;
+
+        let (sender, receiver) = channel(config.capacity);
+        (
+            Arc::new(RwLock::new(Self {
+                pool,
+                replicas,
+                sender,
+                backpressure_policy: config.policy,
+                transaction_options: Default::default(),
+                read_preference: Default::default(),
+                ongoing_transaction: None,
+            })),
+            receiver,
+        )
     }
     pub fn executor(&self) -> Arc<RwLock<Executor>> {
-        RwLock::new(Executor::new(self.pool)).into()
+        self.executor_with_options(self.transaction_options)
+    }
+    /// Executor that begins transaction with the given options rather than the ones set on context.
+    pub fn executor_with_options(&self, options: TransactionOptions) -> Arc<RwLock<Executor>> {
+        RwLock::new(
+            Executor::with_options(self.pool, options)
+                .with_replicas(self.replicas, self.read_preference),
+        )
+        .into()
+    }
+
+    /// Transaction of the outermost unit of work, as long as it is still open.
+    pub(crate) async fn ongoing_transaction(&self) -> Option<OngoingTransaction> {
+        let ongoing = self.ongoing_transaction.as_ref()?;
+        ongoing
+            .executor
+            .read()
+            .await
+            .transaction_scope()
+            .map(|_| ongoing.clone())
     }
 }
 
 #[derive(Debug)]
 pub struct Executor {
     pool: &'static PgPool,
+    replicas: &'static [PgPool],
+    read_preference: ReadPreference,
     transaction: Option<Transaction<'static, Postgres>>,
+    options: TransactionOptions,
+
+    // * Sequence for naming savepoints so that each of them is unique within the transaction.
+    savepoint_seq: usize,
+    // * Sequence of transactions begun, which tells one transaction from another.
+    transaction_seq: usize,
+    abandoned_savepoints: Vec<String>,
 }
 
 impl Executor {
     pub fn new(pool: &'static PgPool) -> Self {
+        Self::with_options(pool, Default::default())
+    }
+    pub fn with_options(pool: &'static PgPool, options: TransactionOptions) -> Self {
         Self {
             pool,
+            replicas: &[],
+            read_preference: Default::default(),
             transaction: None,
+            options: TransactionOptions {
+                statement_timeout: options
+                    .statement_timeout
+                    .or_else(|| config().database.statement_timeout()),
+                ..options
+            },
+            savepoint_seq: 0,
+            transaction_seq: 0,
+            abandoned_savepoints: vec![],
         }
     }
+    pub fn with_replicas(
+        mut self,
+        replicas: &'static [PgPool],
+        read_preference: ReadPreference,
+    ) -> Self {
+        self.replicas = replicas;
+        self.read_preference = read_preference;
+        self
+    }
 
     pub async fn begin(&mut self) -> ApplicationResult<()> {
         match self.transaction.as_mut() {
             None => {
-                self.transaction = Some(
-                    self.pool
-                        .begin()
+                let mut trx = self
+                    .pool
+                    .begin()
+                    .await
+                    .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+                // * SET TRANSACTION must come before any other statement in the transaction.
+                for statement in self.options.statements() {
+                    sqlx::query(&statement)
+                        .execute(&mut trx)
                         .await
-                        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?,
-                );
+                        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+                }
+                self.transaction = Some(trx);
+                self.transaction_seq += 1;
                 Ok(())
             }
             Some(_trx) => {
@@ -65,33 +297,114 @@ impl Executor {
     }
 
     pub async fn commit(&mut self) -> ApplicationResult<()> {
-        if self.transaction.is_none() {
-            panic!("Tranasction Has Not Begun!");
-        };
-
-        let trx = mem::take(&mut self.transaction).unwrap();
+        self.roll_back_abandoned_savepoints().await?;
+        let trx = self.take_transaction()?;
         trx.commit()
             .await
             .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
     }
     pub async fn rollback(&mut self) -> ApplicationResult<()> {
-        if self.transaction.is_none() {
-            panic!("Tranasction Has Not Begun!");
-        };
-
-        let trx = mem::take(&mut self.transaction).unwrap();
+        let trx = self.take_transaction()?;
+        self.abandoned_savepoints.clear();
         trx.rollback()
             .await
             .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
     }
+    fn take_transaction(&mut self) -> ApplicationResult<Transaction<'static, Postgres>> {
+        mem::take(&mut self.transaction).ok_or_else(|| {
+            eprintln!("Transaction Has Not Begun!");
+            ApplicationError::TransactionError
+        })
+    }
 
-    pub fn transaction(&mut self) -> &mut Transaction<'static, Postgres> {
-        match self.transaction.as_mut() {
-            Some(trx) => trx,
-            None => panic!("Transaction Has Not Begun!"),
+    /// Give up on the transaction, or on the savepoint if given, without waiting on the database.
+    /// Transaction given up on is rolled back when it is dropped,
+    /// whereas savepoint given up on is rolled back to on the next use of the executor.
+    pub fn abandon(&mut self, savepoint: Option<&str>) {
+        match savepoint {
+            Some(savepoint) if self.transaction.is_some() => {
+                self.abandoned_savepoints.push(savepoint.into())
+            }
+            Some(_) => (),
+            None => {
+                self.transaction = None;
+                self.abandoned_savepoints.clear();
+            }
+        }
+    }
+
+    /// Undo what has been done within the scopes given up on, leaving the rest of the transaction intact.
+    /// Savepoints are given up on from the innermost, so each of them still exists when rolled back to.
+    async fn roll_back_abandoned_savepoints(&mut self) -> ApplicationResult<()> {
+        for savepoint in mem::take(&mut self.abandoned_savepoints) {
+            eprintln!(
+                "Rolling Back To Abandoned Savepoint! Savepoint:{}",
+                savepoint
+            );
+            self.execute_savepoint_statement(format!("ROLLBACK TO SAVEPOINT {}", savepoint))
+                .await?;
+            self.execute_savepoint_statement(format!("RELEASE SAVEPOINT {}", savepoint))
+                .await?;
         }
+        Ok(())
+    }
+
+    /// Set savepoint within the ongoing transaction and return its name,
+    /// so that the work done afterwards can be rolled back without aborting the whole transaction.
+    pub async fn savepoint(&mut self) -> ApplicationResult<String> {
+        self.transaction().await?;
+        self.savepoint_seq += 1;
+        let savepoint = format!("savepoint_{}", self.savepoint_seq);
+        self.execute_savepoint_statement(format!("SAVEPOINT {}", savepoint))
+            .await?;
+        Ok(savepoint)
+    }
+    pub async fn release_savepoint(&mut self, savepoint: &str) -> ApplicationResult<()> {
+        self.roll_back_abandoned_savepoints().await?;
+        self.execute_savepoint_statement(format!("RELEASE SAVEPOINT {}", savepoint))
+            .await
     }
+    pub async fn rollback_to_savepoint(&mut self, savepoint: &str) -> ApplicationResult<()> {
+        self.roll_back_abandoned_savepoints().await?;
+        self.execute_savepoint_statement(format!("ROLLBACK TO SAVEPOINT {}", savepoint))
+            .await?;
+        self.release_savepoint(savepoint).await
+    }
+    async fn execute_savepoint_statement(&mut self, statement: String) -> ApplicationResult<()> {
+        sqlx::query(&statement)
+            .execute(self.current_transaction()?)
+            .await
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        Ok(())
+    }
+
+    /// Ongoing transaction, out of which the scopes given up on since the last use have been rolled back.
+    pub async fn transaction(&mut self) -> ApplicationResult<&mut Transaction<'static, Postgres>> {
+        self.roll_back_abandoned_savepoints().await?;
+        self.current_transaction()
+    }
+    fn current_transaction(&mut self) -> ApplicationResult<&mut Transaction<'static, Postgres>> {
+        self.transaction.as_mut().ok_or_else(|| {
+            eprintln!("Transaction Has Not Begun!");
+            ApplicationError::TransactionError
+        })
+    }
+    /// Identifier of the ongoing transaction, if any, unique within the executor.
+    pub fn transaction_scope(&self) -> Option<usize> {
+        self.transaction.as_ref().map(|_| self.transaction_seq)
+    }
+    /// Connection to the primary.
     pub fn connection(&self) -> &PgPool {
         self.pool
     }
+    /// Connection for reads outside transaction, which is one of replicas unless primary is preferred or no replica is given.
+    pub fn reader(&self) -> &PgPool {
+        match self.read_preference {
+            ReadPreference::Replica if !self.replicas.is_empty() => {
+                let idx = REPLICA_SEQ.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+                &self.replicas[idx]
+            }
+            _ => self.pool,
+        }
+    }
 }
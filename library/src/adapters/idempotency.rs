@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::commands::{Command, ServiceResponse},
+    utils::{ApplicationError, ApplicationResult},
+};
+
+use super::database::Executor;
+
+/// How long the response to a key is replayed. Once expired, the key can be reused for new request.
+const IDEMPOTENCY_TTL_HOURS: i64 = 24;
+
+/// Idempotency key given by client together with the fingerprint of the command it is sent with.
+#[derive(Debug, Clone)]
+pub struct Idempotency {
+    key: String,
+    fingerprint: String,
+}
+
+impl Idempotency {
+    /// Fingerprint is taken over the command less its `Command::SECRETS`, so that no secret is kept, even hashed.
+    pub fn new<C: Command + Serialize>(
+        key: impl Into<String>,
+        command: &C,
+    ) -> ApplicationResult<Self> {
+        let mut payload = serde_json::to_value(command)
+            .map_err(|err| ApplicationError::SerializationError(Box::new(err)))?;
+        if let Some(fields) = payload.as_object_mut() {
+            for secret in C::SECRETS {
+                fields.remove(*secret);
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(C::NAME);
+        hasher.update(payload.to_string());
+
+        Ok(Self {
+            key: key.into(),
+            fingerprint: format!("{:x}", hasher.finalize()),
+        })
+    }
+
+    fn expiry_cutoff() -> DateTime<Utc> {
+        Utc::now() - Duration::hours(IDEMPOTENCY_TTL_HOURS)
+    }
+
+    /// Look up the response stored for the key.
+    /// Key that was used with different command is a conflict.
+    pub async fn replay(
+        &self,
+        executor: Arc<RwLock<Executor>>,
+    ) -> ApplicationResult<Option<ServiceResponse>> {
+        let record = sqlx::query!(
+            "SELECT fingerprint, response FROM service_idempotency
+            WHERE idempotency_key = $1 AND create_dt > $2",
+            self.key,
+            Self::expiry_cutoff(),
+        )
+        .fetch_optional(executor.read().await.connection())
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+        match record {
+            None => Ok(None),
+            Some(record) if record.fingerprint != self.fingerprint => {
+                Err(ApplicationError::IdempotencyConflict)
+            }
+            Some(record) => match record.response {
+                Some(response) => serde_json::from_str(&response)
+                    .map(Some)
+                    .map_err(|err| ApplicationError::DeserializationError(Box::new(err))),
+                None => Err(ApplicationError::IdempotencyConflict),
+            },
+        }
+    }
+
+    /// Claim the key together with the response to be replayed, within the transaction the command commits with,
+    /// so that the key is never stored without its response.
+    /// Concurrent request with the same key waits for the transaction and then fails with conflict.
+    pub async fn claim(
+        &self,
+        executor: Arc<RwLock<Executor>>,
+        response: &ServiceResponse,
+    ) -> ApplicationResult<()> {
+        let response = serde_json::to_string(response)
+            .map_err(|err| ApplicationError::SerializationError(Box::new(err)))?;
+
+        let res = sqlx::query!(
+            "INSERT INTO service_idempotency (idempotency_key, fingerprint, response) VALUES ($1, $2, $3)
+            ON CONFLICT (idempotency_key) DO UPDATE SET
+                fingerprint = EXCLUDED.fingerprint,
+                response = EXCLUDED.response,
+                create_dt = NOW()
+            WHERE service_idempotency.create_dt <= $4",
+            self.key,
+            self.fingerprint,
+            response,
+            Self::expiry_cutoff(),
+        )
+        .execute(executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+        if res.rows_affected() == 0 {
+            return Err(ApplicationError::IdempotencyConflict);
+        }
+        Ok(())
+    }
+}
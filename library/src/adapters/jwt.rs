@@ -0,0 +1,124 @@
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    bootstrap::{config, connection_pool},
+    domain::auth::entity::{Account, AccountRole, AccountState},
+    utils::{ApplicationError, ApplicationResult},
+};
+
+/// Claims of access token, where `sub` is the id of the account it is issued for and `sid` the session.
+/// Role and whether email is verified are taken as of when the token is issued,
+/// so change to them takes effect on next refresh.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub sid: Uuid,
+    pub role: AccountRole,
+    #[serde(default)]
+    pub verified: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// JWT access token signed with HS256 over `auth.jwt_secret`.
+pub struct AccessToken;
+
+impl AccessToken {
+    pub fn issue(account: &Account, session_id: Uuid) -> ApplicationResult<String> {
+        let auth = &config().auth;
+        let now = Utc::now();
+        let claims = Claims {
+            sub: account.id.clone(),
+            sid: session_id,
+            role: account.role,
+            verified: account.state == AccountState::Created,
+            iat: now.timestamp(),
+            exp: (now + auth.access_token_ttl()).timestamp(),
+        };
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(auth.jwt_secret.as_bytes()),
+        )
+        .map_err(|err| ApplicationError::InvalidConfig(format!("auth.jwt_secret: {}", err)))
+    }
+
+    /// Claims of the token given, which must be signed with the key configured and not expired.
+    pub fn verify(token: &str) -> ApplicationResult<Claims> {
+        let auth = &config().auth;
+        jsonwebtoken::decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(auth.jwt_secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .map(|data| data.claims)
+        .map_err(|_| ApplicationError::InvalidToken)
+    }
+
+    /// Claims of the token given, whose session must not have been revoked nor expired on top of `verify`.
+    /// Session is looked up on primary by its key, so that revocation takes effect right away.
+    pub async fn verify_active(token: &str) -> ApplicationResult<Claims> {
+        let claims = Self::verify(token)?;
+        let active = sqlx::query_scalar!(
+            r#"SELECT EXISTS(
+                SELECT 1 FROM auth_token_stat WHERE id = $1 AND expiry_date > NOW()
+            ) AS "active!""#,
+            claims.sid
+        )
+        .fetch_one(connection_pool().await)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        if !active {
+            return Err(ApplicationError::InvalidToken);
+        }
+        Ok(claims)
+    }
+}
+
+/// Claims of MFA token handed out on password passed, for the account with second factor.
+/// It is told apart from access token by `mfa`, so neither can be used in place of the other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MfaClaims {
+    pub sub: String,
+    pub mfa: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Short-lived JWT that is exchanged for tokens together with second factor, signed the same way as access token.
+pub struct MfaToken;
+
+impl MfaToken {
+    pub fn issue(account: &Account) -> ApplicationResult<String> {
+        let auth = &config().auth;
+        let now = Utc::now();
+        let claims = MfaClaims {
+            sub: account.id.clone(),
+            mfa: true,
+            iat: now.timestamp(),
+            exp: (now + auth.mfa_token_ttl()).timestamp(),
+        };
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(auth.jwt_secret.as_bytes()),
+        )
+        .map_err(|err| ApplicationError::InvalidConfig(format!("auth.jwt_secret: {}", err)))
+    }
+
+    pub fn verify(token: &str) -> ApplicationResult<MfaClaims> {
+        let auth = &config().auth;
+        jsonwebtoken::decode::<MfaClaims>(
+            token,
+            &DecodingKey::from_secret(auth.jwt_secret.as_bytes()),
+            &Validation::new(Algorithm::HS256),
+        )
+        .ok()
+        .map(|data| data.claims)
+        .filter(|claims| claims.mfa)
+        .ok_or(ApplicationError::InvalidToken)
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::utils::{ApplicationError, ApplicationResult};
+
+use super::database::Executor;
+
+/// Audit record of login attempt, successful or not.
+/// Account is absent when login is attempted with unknown email.
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    pub id: Uuid,
+    pub account_id: Option<String>,
+    pub email: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub succeeded: bool,
+    pub create_dt: DateTime<Utc>,
+}
+
+impl LoginAttempt {
+    pub fn new(
+        account_id: Option<String>,
+        email: impl Into<String>,
+        ip: Option<String>,
+        user_agent: Option<String>,
+        succeeded: bool,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            account_id,
+            email: email.into(),
+            ip,
+            user_agent,
+            succeeded,
+            create_dt: Utc::now(),
+        }
+    }
+
+    /// Record the attempt within the executor's transaction.
+    pub async fn add(&self, executor: Arc<RwLock<Executor>>) -> ApplicationResult<()> {
+        sqlx::query!(
+            "INSERT INTO auth_login_attempt
+            (id, account_id, email, ip, user_agent, succeeded, create_dt) VALUES
+            ($1, $2, $3, $4, $5, $6, $7)",
+            self.id,
+            self.account_id,
+            self.email,
+            self.ip,
+            self.user_agent,
+            self.succeeded,
+            self.create_dt,
+        )
+        .execute(executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        Ok(())
+    }
+
+    /// Number of failed attempts on the account since `since`, not counting the ones before the last successful attempt.
+    pub async fn count_failures(
+        executor: Arc<RwLock<Executor>>,
+        account_id: &str,
+        since: DateTime<Utc>,
+    ) -> ApplicationResult<i64> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM auth_login_attempt
+            WHERE account_id = $1
+                AND NOT succeeded
+                AND create_dt > $2
+                AND create_dt > COALESCE(
+                    (SELECT MAX(create_dt) FROM auth_login_attempt WHERE account_id = $1 AND succeeded),
+                    '-infinity'
+                )
+            "#,
+            account_id,
+            since,
+        )
+        .fetch_one(executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
+    }
+
+    /// Attempts on the account, latest first.
+    pub async fn list(
+        executor: Arc<RwLock<Executor>>,
+        account_id: &str,
+    ) -> ApplicationResult<Vec<Self>> {
+        sqlx::query_as!(
+            Self,
+            "SELECT id, account_id, email, ip, user_agent, succeeded, create_dt
+            FROM auth_login_attempt WHERE account_id = $1 ORDER BY create_dt DESC",
+            account_id,
+        )
+        .fetch_all(executor.read().await.connection())
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    config::MailConfig,
+    domain::auth::events::{
+        AccountLocked, AccountUnblocked, PasswordResetRequested, VerificationRequested,
+    },
+    utils::{ApplicationError, ApplicationResult},
+};
+
+/// Where mails are delivered to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailTransport {
+    /// Written as file under `mail.dir`, one per mail.
+    #[default]
+    File,
+    /// Kept in memory, which is for tests.
+    Memory,
+}
+
+impl std::str::FromStr for MailTransport {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(Self::File),
+            "memory" => Ok(Self::Memory),
+            _ => Err(format!("expected file or memory but got {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    pub fn verification(from: &str, event: &VerificationRequested) -> Self {
+        Self {
+            from: from.into(),
+            to: event.email.clone(),
+            subject: "Verify your email".into(),
+            body: format!(
+                "Verify your email with the token below, which expires at {}.\n\n{}\n",
+                event.expiry_date.to_rfc3339(),
+                event.token
+            ),
+        }
+    }
+
+    pub fn password_reset(from: &str, event: &PasswordResetRequested) -> Self {
+        Self {
+            from: from.into(),
+            to: event.email.clone(),
+            subject: "Reset your password".into(),
+            body: format!(
+                "Reset your password with the token below, which expires at {}.\nIgnore this mail if you didn't request it.\n\n{}\n",
+                event.expiry_date.to_rfc3339(),
+                event.token
+            ),
+        }
+    }
+
+    pub fn account_locked(from: &str, event: &AccountLocked) -> Self {
+        Self {
+            from: from.into(),
+            to: event.email.clone(),
+            subject: "Your account is locked".into(),
+            body: format!(
+                "Your account is locked until {} after {} failed sign-in attempts.\nReset your password if you didn't attempt them.\n",
+                event.locked_until.to_rfc3339(),
+                event.failed_attempts
+            ),
+        }
+    }
+
+    pub fn account_unblocked(from: &str, event: &AccountUnblocked) -> Self {
+        Self {
+            from: from.into(),
+            to: event.email.clone(),
+            subject: "Your account is unblocked".into(),
+            body: "Your account is unblocked and you can sign in again.\n".into(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> ApplicationResult<()>;
+}
+
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> ApplicationResult<()> {
+        // * Recipient is not part of the file name, as it could point outside of the directory.
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S%6f"),
+            Uuid::new_v4()
+        ));
+        let content = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}",
+            mail.from, mail.to, mail.subject, mail.body
+        );
+        let dir = self.dir.clone();
+        tokio::task::spawn_blocking(move || {
+            fs::create_dir_all(dir)?;
+            fs::write(path, content)
+        })
+        .await
+        .map_err(|err| ApplicationError::MailDeliveryError(err.to_string()))?
+        .map_err(|err| ApplicationError::MailDeliveryError(err.to_string()))
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryMailer {
+    sent: Mutex<Vec<Mail>>,
+}
+
+impl InMemoryMailer {
+    /// Mails sent so far, which are then cleared.
+    pub fn take_sent(&self) -> Vec<Mail> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, mail: Mail) -> ApplicationResult<()> {
+        self.sent.lock().unwrap().push(mail);
+        Ok(())
+    }
+}
+
+/// Mailer configured under `mail`.
+pub fn mailer(mail: &MailConfig) -> Arc<dyn Mailer> {
+    match mail.transport {
+        MailTransport::File => Arc::new(FileMailer::new(&mail.dir)),
+        MailTransport::Memory => Arc::new(InMemoryMailer::default()),
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    PgPool,
+};
+
+use crate::utils::{ApplicationError, ApplicationResult};
+
+/// Migrations under `/migrations`, embedded at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../migrations");
+
+/// What to do with the database schema on startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationMode {
+    /// Apply pending migrations, refusing to boot on drift.
+    Apply,
+    /// Refuse to boot on drift or on any pending migration.
+    #[default]
+    Verify,
+}
+
+impl std::str::FromStr for MigrationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "apply" => Ok(Self::Apply),
+            "verify" => Ok(Self::Verify),
+            _ => Err(format!("expected apply or verify but got {:?}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the migration embedded has been changed since.
+    ChecksumMismatch,
+    /// Applied, but failed halfway.
+    Dirty,
+    /// Applied, but not known to this build.
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+impl MigrationStatus {
+    /// Whether the database schema no longer matches the migrations embedded.
+    pub fn is_drift(&self) -> bool {
+        !matches!(
+            self.state,
+            MigrationState::Applied | MigrationState::Pending
+        )
+    }
+}
+
+pub struct Migration;
+
+impl Migration {
+    /// Status of every embedded migration, followed by the ones applied but unknown to this build.
+    pub async fn status(pool: &PgPool) -> ApplicationResult<Vec<MigrationStatus>> {
+        let mut conn = pool.acquire().await.map_err(Self::error)?;
+        conn.ensure_migrations_table()
+            .await
+            .map_err(Self::migrate_error)?;
+        let dirty_version = conn.dirty_version().await.map_err(Self::migrate_error)?;
+        let mut applied: HashMap<i64, Vec<u8>> = conn
+            .list_applied_migrations()
+            .await
+            .map_err(Self::migrate_error)?
+            .into_iter()
+            .map(|migration| (migration.version, migration.checksum.into_owned()))
+            .collect();
+
+        let mut statuses = vec![];
+        for migration in MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+        {
+            let state = match applied.remove(&migration.version) {
+                _ if dirty_version == Some(migration.version) => MigrationState::Dirty,
+                Some(checksum) if checksum == *migration.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::ChecksumMismatch,
+                None => MigrationState::Pending,
+            };
+            statuses.push(MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            });
+        }
+
+        let mut missing: Vec<i64> = applied.into_keys().collect();
+        missing.sort();
+        statuses.extend(missing.into_iter().map(|version| MigrationStatus {
+            version,
+            description: Default::default(),
+            state: MigrationState::Missing,
+        }));
+        Ok(statuses)
+    }
+
+    /// Apply pending migrations and return their versions. Nothing is applied on dry run.
+    pub async fn up(pool: &PgPool, dry_run: bool) -> ApplicationResult<Vec<i64>> {
+        let statuses = Self::status(pool).await?;
+        Self::refuse_drift(&statuses)?;
+
+        let pending: Vec<i64> = statuses
+            .iter()
+            .filter(|status| status.state == MigrationState::Pending)
+            .map(|status| status.version)
+            .collect();
+        if !dry_run && !pending.is_empty() {
+            MIGRATOR.run(pool).await.map_err(Self::migrate_error)?;
+        }
+        Ok(pending)
+    }
+
+    /// Revert the latest migration applied and return its version. Nothing is reverted on dry run.
+    pub async fn down(pool: &PgPool, dry_run: bool) -> ApplicationResult<Option<i64>> {
+        let statuses = Self::status(pool).await?;
+        Self::refuse_drift(&statuses)?;
+
+        let mut applied = statuses
+            .iter()
+            .filter(|status| status.state == MigrationState::Applied)
+            .map(|status| status.version)
+            .rev();
+        let Some(latest) = applied.next() else {
+            return Ok(None);
+        };
+        if !dry_run {
+            let target = applied.next().unwrap_or(0);
+            MIGRATOR
+                .undo(pool, target)
+                .await
+                .map_err(Self::migrate_error)?;
+        }
+        Ok(Some(latest))
+    }
+
+    /// Bring the schema in line with the embedded migrations as `mode` dictates, before the application boots.
+    pub async fn ensure(pool: &PgPool, mode: MigrationMode) -> ApplicationResult<()> {
+        match mode {
+            MigrationMode::Apply => {
+                let applied = Self::up(pool, false).await?;
+                if !applied.is_empty() {
+                    println!("Migrations Applied! Versions:{:?}", applied);
+                }
+            }
+            MigrationMode::Verify => {
+                let statuses = Self::status(pool).await?;
+                Self::refuse_drift(&statuses)?;
+                let pending: Vec<i64> = statuses
+                    .iter()
+                    .filter(|status| status.state == MigrationState::Pending)
+                    .map(|status| status.version)
+                    .collect();
+                if !pending.is_empty() {
+                    Err(ApplicationError::SchemaDrift(format!(
+                        "pending migrations {:?}",
+                        pending
+                    )))?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn refuse_drift(statuses: &[MigrationStatus]) -> ApplicationResult<()> {
+        let drift: Vec<String> = statuses
+            .iter()
+            .filter(|status| status.is_drift())
+            .map(|status| format!("{}({:?})", status.version, status.state))
+            .collect();
+        if drift.is_empty() {
+            Ok(())
+        } else {
+            Err(ApplicationError::SchemaDrift(drift.join(", ")))
+        }
+    }
+
+    fn error(err: sqlx::Error) -> ApplicationError {
+        ApplicationError::DatabaseConnectionError(Box::new(err))
+    }
+    fn migrate_error(err: MigrateError) -> ApplicationError {
+        ApplicationError::DatabaseConnectionError(Box::new(err))
+    }
+}
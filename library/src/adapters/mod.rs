@@ -1,3 +1,12 @@
 pub mod database;
+pub mod idempotency;
+pub mod jwt;
+pub mod login_attempt;
+pub mod mailer;
+pub mod migration;
 pub mod outbox;
+pub mod password;
+pub mod process;
 pub mod repositories;
+pub mod scheduled_command;
+pub mod totp;
@@ -6,7 +6,14 @@ use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
-    domain::{board::events::BoardCreated, commands::Command, Message},
+    domain::{
+        auth::events::{
+            AccountLocked, AccountUnblocked, PasswordResetRequested, VerificationRequested,
+        },
+        board::events::BoardCreated,
+        commands::Command,
+        Message,
+    },
     utils::{ApplicationError, ApplicationResult},
 };
 
@@ -25,10 +32,8 @@ pub struct Outbox {
 macro_rules! convert_event {
     ( $obj:expr, $( $type: ty ), * ) => {
         match $obj.topic.as_str() {
-          $(stringify!($type)=> serde_json::from_str::<$type>($obj.state.as_str()).expect("Given type not deserializable!").message_clone() ,)*
-          _ => {
-                panic!("Such event not allowed to process through outbox.");
-          }
+          $(stringify!($type)=> Some(serde_json::from_str::<$type>($obj.state.as_str()).expect("Given type not deserializable!").message_clone()) ,)*
+          _ => None,
         }
     };
 }
@@ -45,14 +50,37 @@ impl Outbox {
         }
     }
     pub fn convert_event(&self) -> Box<dyn Message> {
+        self.try_convert_event()
+            .expect("Such event not allowed to process through outbox.")
+    }
+    /// Event held in the outbox, or `None` when its topic is not allowed to process through outbox.
+    pub fn try_convert_event(&self) -> Option<Box<dyn Message>> {
         // convert event. it takes outbox reference and target type that is to be deserialized.
         // you can insert any number of desired type as long as it is outboxable type.
-        convert_event!(self, BoardCreated)
+        convert_event!(
+            self,
+            BoardCreated,
+            VerificationRequested,
+            PasswordResetRequested,
+            AccountLocked,
+            AccountUnblocked
+        )
     }
     pub fn tag_processed(&mut self) {
         self.processed = true
     }
 
+    /// Blank out the field of the state, as in token that is not to be kept in plain once it is delivered.
+    pub fn scrub(&mut self, field: &str) -> ApplicationResult<()> {
+        let mut state: serde_json::Value = serde_json::from_str(&self.state)
+            .map_err(|err| ApplicationError::DeserializationError(Box::new(err)))?;
+        if let Some(value) = state.get_mut(field) {
+            *value = serde_json::Value::String(String::new());
+        }
+        self.state = state.to_string();
+        Ok(())
+    }
+
     pub async fn add(
         connection: Arc<RwLock<Executor>>,
         outboxes: Vec<Self>,
@@ -71,7 +99,7 @@ impl Outbox {
                 ob.processed,
                 ob.create_dt,
             )
-            .execute(connection.write().await.connection())
+            .execute(connection.write().await.transaction().await?)
             .await
             .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
         }
@@ -95,13 +123,15 @@ impl Outbox {
             Self,
             r#" 
                 UPDATE service_outbox SET 
-                processed =$1
-                WHERE id = $2
+                processed =$1,
+                state = $2
+                WHERE id = $3
             "#,
             true,
+            self.state,
             self.id,
         )
-        .execute(executor.write().await.transaction())
+        .execute(executor.write().await.transaction().await?)
         .await
         .map_err(|err| {
             eprintln!("{}", err);
@@ -111,4 +141,6 @@ impl Outbox {
     }
 }
 
-impl Command for Outbox {}
+impl Command for Outbox {
+    const NAME: &'static str = "Outbox";
+}
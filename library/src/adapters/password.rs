@@ -0,0 +1,217 @@
+use std::sync::{Arc, OnceLock};
+
+use argon2::{
+    password_hash::{
+        rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Params, Version,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::AuthConfig,
+    utils::{ApplicationError, ApplicationResult},
+};
+
+/// Algorithm new passwords are hashed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasswordHasherKind {
+    #[default]
+    Argon2id,
+    Bcrypt,
+}
+
+impl std::str::FromStr for PasswordHasherKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "argon2id" => Ok(Self::Argon2id),
+            "bcrypt" => Ok(Self::Bcrypt),
+            _ => Err(format!("expected argon2id or bcrypt but got {:?}", s)),
+        }
+    }
+}
+
+// * Password of no one, hashed to verify against when there is no account to verify against.
+const DUMMY_PASSWORD: &str = "dummy-password";
+
+/// Hashes passwords with one algorithm, while verifying hashes made by any algorithm supported.
+/// Hash made by other algorithm, or with other cost, is to be rehashed once its password is verified.
+pub trait PasswordHasher: Send + Sync {
+    fn hash(&self, password: &str) -> ApplicationResult<String>;
+
+    fn needs_rehash(&self, hashed_password: &str) -> bool;
+
+    /// Hash of `DUMMY_PASSWORD` made once the way new passwords are,
+    /// so that verifying against it takes as long as verifying against real one.
+    fn dummy_hash(&self) -> ApplicationResult<&str>;
+
+    fn verify(&self, password: &str, hashed_password: &str) -> bool {
+        if hashed_password.starts_with("$argon2") {
+            PasswordHash::new(hashed_password)
+                .map(|hash| {
+                    Argon2::default()
+                        .verify_password(password.as_bytes(), &hash)
+                        .is_ok()
+                })
+                .unwrap_or(false)
+        } else if hashed_password.starts_with("$2") {
+            bcrypt::verify(password, hashed_password).unwrap_or(false)
+        } else {
+            false
+        }
+    }
+}
+
+pub struct Argon2Hasher {
+    params: Params,
+    dummy_hash: OnceLock<String>,
+}
+
+impl Argon2Hasher {
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> ApplicationResult<Self> {
+        let params = Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(|err| ApplicationError::InvalidConfig(format!("argon2: {}", err)))?;
+        Ok(Self {
+            params,
+            dummy_hash: OnceLock::new(),
+        })
+    }
+}
+
+impl PasswordHasher for Argon2Hasher {
+    fn hash(&self, password: &str) -> ApplicationResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|err| ApplicationError::PasswordHashingError(err.to_string()))
+    }
+
+    fn needs_rehash(&self, hashed_password: &str) -> bool {
+        let Ok(hash) = PasswordHash::new(hashed_password) else {
+            return true;
+        };
+        hash.algorithm != Algorithm::Argon2id.ident()
+            || hash.version != Some(Version::V0x13.into())
+            || Params::try_from(&hash).ok().as_ref().map(|params| {
+                (params.m_cost(), params.t_cost(), params.p_cost())
+            }) != Some((
+                self.params.m_cost(),
+                self.params.t_cost(),
+                self.params.p_cost(),
+            ))
+    }
+
+    fn dummy_hash(&self) -> ApplicationResult<&str> {
+        cached_hash(&self.dummy_hash, || self.hash(DUMMY_PASSWORD))
+    }
+}
+
+pub struct BcryptHasher {
+    cost: u32,
+    dummy_hash: OnceLock<String>,
+}
+
+impl BcryptHasher {
+    pub fn new(cost: u32) -> Self {
+        Self {
+            cost,
+            dummy_hash: OnceLock::new(),
+        }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn hash(&self, password: &str) -> ApplicationResult<String> {
+        bcrypt::hash(password, self.cost)
+            .map_err(|err| ApplicationError::PasswordHashingError(err.to_string()))
+    }
+
+    fn needs_rehash(&self, hashed_password: &str) -> bool {
+        hashed_password
+            .parse::<bcrypt::HashParts>()
+            .map(|parts| parts.get_cost() != self.cost)
+            .unwrap_or(true)
+    }
+
+    fn dummy_hash(&self) -> ApplicationResult<&str> {
+        cached_hash(&self.dummy_hash, || self.hash(DUMMY_PASSWORD))
+    }
+}
+
+fn cached_hash(
+    cell: &OnceLock<String>,
+    hash: impl FnOnce() -> ApplicationResult<String>,
+) -> ApplicationResult<&str> {
+    if let Some(hashed) = cell.get() {
+        return Ok(hashed);
+    }
+    let hashed = hash()?;
+    Ok(cell.get_or_init(|| hashed))
+}
+
+/// Hasher configured under `auth`.
+pub fn password_hasher(auth: &AuthConfig) -> ApplicationResult<Arc<dyn PasswordHasher>> {
+    Ok(match auth.password_hasher {
+        PasswordHasherKind::Argon2id => Arc::new(Argon2Hasher::new(
+            auth.argon2_memory_kib,
+            auth.argon2_iterations,
+            auth.argon2_parallelism,
+        )?),
+        PasswordHasherKind::Bcrypt => Arc::new(BcryptHasher::new(auth.bcrypt_cost)),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordVerification {
+    Invalid,
+    Verified,
+    /// Verified against outdated hash, which is to be replaced with the one given.
+    Rehashed(String),
+}
+
+// * Hashing is deliberately slow, so it is kept off the async workers.
+
+pub async fn hash_password(
+    hasher: Arc<dyn PasswordHasher>,
+    password: String,
+) -> ApplicationResult<String> {
+    tokio::task::spawn_blocking(move || hasher.hash(&password))
+        .await
+        .map_err(|err| ApplicationError::PasswordHashingError(err.to_string()))?
+}
+
+pub async fn verify_password(
+    hasher: Arc<dyn PasswordHasher>,
+    password: String,
+    hashed_password: String,
+) -> ApplicationResult<PasswordVerification> {
+    tokio::task::spawn_blocking(move || {
+        if !hasher.verify(&password, &hashed_password) {
+            Ok(PasswordVerification::Invalid)
+        } else if hasher.needs_rehash(&hashed_password) {
+            hasher.hash(&password).map(PasswordVerification::Rehashed)
+        } else {
+            Ok(PasswordVerification::Verified)
+        }
+    })
+    .await
+    .map_err(|err| ApplicationError::PasswordHashingError(err.to_string()))?
+}
+
+/// Verify password for the account that doesn't exist, which is never verified
+/// but takes as long as it does for the account that exists.
+pub async fn verify_dummy_password(
+    hasher: Arc<dyn PasswordHasher>,
+    password: String,
+) -> ApplicationResult<PasswordVerification> {
+    tokio::task::spawn_blocking(move || {
+        hasher.verify(&password, hasher.dummy_hash()?);
+        Ok(PasswordVerification::Invalid)
+    })
+    .await
+    .map_err(|err| ApplicationError::PasswordHashingError(err.to_string()))?
+}
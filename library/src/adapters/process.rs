@@ -0,0 +1,239 @@
+use std::{mem, sync::Arc};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{
+    domain::commands::Command,
+    utils::{ApplicationError, ApplicationResult},
+};
+
+use super::{database::Executor, scheduled_command::ScheduledCommand};
+
+/// Process manager coordinates steps that span multiple aggregates.
+/// Its state is persisted keyed by correlation id, and it moves on by issuing follow-up commands.
+pub trait ProcessManager: Serialize + DeserializeOwned + Send + Sync {
+    /// Name under which the process is persisted, so it must not change once processes are stored.
+    const PROCESS_TYPE: &'static str;
+
+    /// Commands that undo the steps taken so far, issued when the process fails or times out.
+    fn compensations(&self) -> ApplicationResult<Vec<ScheduledCommand>>;
+}
+
+#[derive(Eq, PartialEq, Clone, Hash, sqlx::Type, Debug)]
+#[sqlx(type_name = "process_status")]
+pub enum ProcessStatus {
+    Running,
+    Completed,
+    Compensated,
+    TimedOut,
+}
+
+struct ProcessRecord {
+    correlation_id: String,
+    status: ProcessStatus,
+    state: String,
+    deadline: Option<DateTime<Utc>>,
+    version: i32,
+}
+
+pub struct Process<P: ProcessManager> {
+    pub correlation_id: String,
+    pub status: ProcessStatus,
+    pub state: P,
+    pub deadline: Option<DateTime<Utc>>,
+    version: i32,
+    is_new: bool,
+
+    // * Commands issued since the process was loaded, persisted together with the process.
+    commands: Vec<ScheduledCommand>,
+}
+
+impl<P: ProcessManager> Process<P> {
+    /// Start new process. Process that is still running after `timeout` is compensated by the scheduler.
+    pub fn start(correlation_id: impl Into<String>, state: P, timeout: Option<Duration>) -> Self {
+        Self {
+            correlation_id: correlation_id.into(),
+            status: ProcessStatus::Running,
+            state,
+            deadline: timeout.map(|timeout| Utc::now() + timeout),
+            version: 0,
+            is_new: true,
+            commands: vec![],
+        }
+    }
+
+    /// Issue follow-up command to be dispatched through `MessageBus`.
+    /// `step` identifies the command within the process so that the same step is never issued twice.
+    pub fn issue<C>(&mut self, step: &str, command: &C) -> ApplicationResult<()>
+    where
+        C: Command + Serialize,
+    {
+        let scheduled_command =
+            ScheduledCommand::new(command, Utc::now())?.with_dedupe_key(self.dedupe_key(step));
+        self.commands.push(scheduled_command);
+        Ok(())
+    }
+
+    pub fn complete(&mut self) {
+        self.status = ProcessStatus::Completed
+    }
+
+    pub fn compensate(&mut self) -> ApplicationResult<()> {
+        self.issue_compensations()?;
+        self.status = ProcessStatus::Compensated;
+        Ok(())
+    }
+
+    fn time_out(&mut self) -> ApplicationResult<()> {
+        self.issue_compensations()?;
+        self.status = ProcessStatus::TimedOut;
+        Ok(())
+    }
+
+    fn issue_compensations(&mut self) -> ApplicationResult<()> {
+        for (idx, compensation) in self.state.compensations()?.into_iter().enumerate() {
+            let dedupe_key = self.dedupe_key(&format!("compensation-{}", idx));
+            self.commands.push(compensation.with_dedupe_key(dedupe_key));
+        }
+        Ok(())
+    }
+
+    fn dedupe_key(&self, step: &str) -> String {
+        format!("{}:{}:{}", P::PROCESS_TYPE, self.correlation_id, step)
+    }
+
+    fn from_record(record: ProcessRecord) -> ApplicationResult<Self> {
+        Ok(Self {
+            correlation_id: record.correlation_id,
+            status: record.status,
+            state: serde_json::from_str(&record.state)
+                .map_err(|err| ApplicationError::DeserializationError(Box::new(err)))?,
+            deadline: record.deadline,
+            version: record.version,
+            is_new: false,
+            commands: vec![],
+        })
+    }
+
+    pub async fn get(
+        executor: Arc<RwLock<Executor>>,
+        correlation_id: &str,
+    ) -> ApplicationResult<Self> {
+        let record = sqlx::query_as!(
+            ProcessRecord,
+            r#"
+            SELECT
+                correlation_id,
+                status AS "status: ProcessStatus",
+                state,
+                deadline,
+                version
+            FROM service_process
+            WHERE process_type = $1 AND correlation_id = $2
+            "#,
+            P::PROCESS_TYPE,
+            correlation_id
+        )
+        .fetch_optional(executor.read().await.connection())
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?
+        .ok_or(ApplicationError::EntityNotFound)?;
+
+        Self::from_record(record)
+    }
+
+    /// Persist process state together with the commands it has issued, within the executor's transaction.
+    pub async fn save(&mut self, executor: Arc<RwLock<Executor>>) -> ApplicationResult<()> {
+        let state = serde_json::to_string(&self.state)
+            .map_err(|err| ApplicationError::SerializationError(Box::new(err)))?;
+
+        let res = if self.is_new {
+            sqlx::query!(
+                "INSERT INTO service_process
+                (process_type, correlation_id, status, state, deadline, version) VALUES
+                ($1, $2, $3, $4, $5, $6)",
+                P::PROCESS_TYPE,
+                self.correlation_id,
+                self.status.clone() as ProcessStatus,
+                state,
+                self.deadline,
+                self.version,
+            )
+            .execute(executor.write().await.transaction().await?)
+            .await
+        } else {
+            sqlx::query!(
+                "UPDATE service_process SET
+                status = $1,
+                state = $2,
+                deadline = $3,
+                version = $4
+                WHERE process_type = $5 AND correlation_id = $6 AND version = $7",
+                self.status.clone() as ProcessStatus,
+                state,
+                self.deadline,
+                self.version + 1,
+                P::PROCESS_TYPE,
+                self.correlation_id,
+                self.version,
+            )
+            .execute(executor.write().await.transaction().await?)
+            .await
+        }
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+        if res.rows_affected() == 0 {
+            eprintln!(
+                "Process Has Been Changed Concurrently! Process:{} CorrelationId:{}",
+                P::PROCESS_TYPE,
+                self.correlation_id
+            );
+            return Err(ApplicationError::ConcurrencyConflict);
+        }
+        if !self.is_new {
+            self.version += 1;
+        }
+        self.is_new = false;
+
+        for command in mem::take(&mut self.commands) {
+            command
+                .insert(executor.write().await.transaction().await?)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Compensate every running process whose deadline has passed and return the number of processes timed out.
+    /// Processes are locked within the executor's transaction so that concurrent workers skip over them.
+    pub async fn time_out_expired(executor: Arc<RwLock<Executor>>) -> ApplicationResult<usize> {
+        let records = sqlx::query_as!(
+            ProcessRecord,
+            r#"
+            SELECT
+                correlation_id,
+                status AS "status: ProcessStatus",
+                state,
+                deadline,
+                version
+            FROM service_process
+            WHERE process_type = $1 AND status = $2 AND deadline <= NOW()
+            FOR UPDATE SKIP LOCKED
+            "#,
+            P::PROCESS_TYPE,
+            ProcessStatus::Running as ProcessStatus,
+        )
+        .fetch_all(executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+        let timed_out = records.len();
+        for record in records {
+            let mut process = Self::from_record(record)?;
+            process.time_out()?;
+            process.save(executor.clone()).await?;
+        }
+        Ok(timed_out)
+    }
+}
@@ -1,23 +1,582 @@
+use crate::domain::auth::entity::{
+    Account, AccountRole, AccountState, EmailVerification, PasswordReset, TokenStat,
+    TotpCredential,
+};
 
 use crate::domain::auth::AuthAggregate;
 
-use super::{Repository};
+use crate::domain::builder::*;
+
 use crate::utils::ApplicationError;
 
+use std::mem;
+
+use uuid::Uuid;
+
+use sqlx::PgConnection;
 
+use super::Repository;
 
 impl Repository<AuthAggregate> {
- 
+    pub async fn add(&mut self, aggregate: &mut AuthAggregate) -> Result<String, ApplicationError> {
+        self.set_events(mem::take(&mut aggregate.events));
+        self._add(aggregate).await
+    }
+    pub async fn update(&mut self, aggregate: &mut AuthAggregate) -> Result<(), ApplicationError> {
+        self.set_events(mem::take(&mut aggregate.events));
+        self._update(aggregate).await
+    }
+
+    pub async fn _add(&mut self, aggregate: &AuthAggregate) -> Result<String, ApplicationError> {
+        let account = &aggregate.account;
+
+        sqlx::query!(
+            "INSERT INTO auth_account (id, email, state, role, hashed_password, nickname, create_dt, locked_until, version) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+            account.id,
+            account.email,
+            account.state.clone() as AccountState,
+            account.role as AccountRole,
+            account.hashed_password,
+            account.nickname,
+            account.create_dt,
+            account.locked_until,
+            account.version,
+        )
+        .execute(self.executor.write().await.transaction().await?)
+        .await
+        .map_err(Self::conflict_or_error)?;
+
+        for session in aggregate.sessions.iter() {
+            self.save_session(&account.id, session).await?;
+        }
+        if let Some(verification) = aggregate.verification.as_ref() {
+            self.save_verification(&account.id, verification).await?;
+        }
+        if let Some(password_reset) = aggregate.password_reset.as_ref() {
+            self.save_password_reset(&account.id, password_reset).await?;
+        }
+        if let Some(totp) = aggregate.totp.as_ref() {
+            self.save_totp(&account.id, totp).await?;
+        }
+
+        let scope = self.executor.read().await.transaction_scope();
+        self.identity_map
+            .write()
+            .await
+            .insert(scope, account.id.clone(), Self::snapshot(aggregate));
+        Ok(account.id.clone())
+    }
+
+    /// Aggregate already loaded within the transaction is given out of identity map without querying.
+    pub async fn get(&self, aggregate_id: &str) -> Result<AuthAggregate, ApplicationError> {
+        let scope = self.executor.read().await.transaction_scope();
+        if let Some(aggregate) = self.identity_map.read().await.get(scope, aggregate_id) {
+            return Ok(Self::snapshot(aggregate));
+        }
+
+        // * Read within transaction goes to the primary, otherwise to replica.
+        let mut executor = self.executor.write().await;
+        let mut pool_connection;
+        let connection: &mut PgConnection = match scope {
+            Some(_) => executor.transaction().await?,
+            None => {
+                pool_connection = executor
+                    .reader()
+                    .acquire()
+                    .await
+                    .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+                &mut pool_connection
+            }
+        };
+
+        let account = sqlx::query_as!(
+            Account,
+            r#"
+            SELECT
+                id,
+                email,
+                state AS "state: AccountState",
+                role AS "role: AccountRole",
+                hashed_password,
+                nickname,
+                create_dt,
+                locked_until,
+                version
+            FROM auth_account
+            WHERE id = $1
+            "#,
+            aggregate_id
+        )
+        .fetch_one(&mut *connection)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })?;
+
+        let sessions = sqlx::query_as!(
+            TokenStat,
+            r#"
+            SELECT
+                id,
+                refresh_token_hash,
+                rotated_refresh_token_hashes,
+                expiry_date,
+                user_agent,
+                ip,
+                create_dt,
+                last_seen_dt
+            FROM auth_token_stat
+            WHERE account_id = $1
+            ORDER BY create_dt
+            "#,
+            aggregate_id
+        )
+        .fetch_all(&mut *connection)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })?;
+
+        let verification = sqlx::query_as!(
+            EmailVerification,
+            r#"
+            SELECT
+                token_hash,
+                expiry_date,
+                requested_dt
+            FROM auth_email_verification
+            WHERE account_id = $1
+            "#,
+            aggregate_id
+        )
+        .fetch_optional(&mut *connection)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })?;
+
+        let password_reset = sqlx::query_as!(
+            PasswordReset,
+            r#"
+            SELECT
+                token_hash,
+                expiry_date,
+                requested_dt
+            FROM auth_password_reset
+            WHERE account_id = $1
+            "#,
+            aggregate_id
+        )
+        .fetch_optional(&mut *connection)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })?;
+
+        let totp = sqlx::query_as!(
+            TotpCredential,
+            r#"
+            SELECT
+                encrypted_secret,
+                confirmed,
+                recovery_code_hashes,
+                last_used_step
+            FROM auth_totp
+            WHERE account_id = $1
+            "#,
+            aggregate_id
+        )
+        .fetch_optional(connection)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })?;
+
+        //*  Build auth aggregate
+        let mut auth_aggregate = AuthAggregate::builder().take_account(account).build();
+        auth_aggregate.sessions = sessions;
+        auth_aggregate.verification = verification;
+        auth_aggregate.password_reset = password_reset;
+        auth_aggregate.totp = totp;
+        self.identity_map.write().await.insert(
+            scope,
+            aggregate_id.into(),
+            Self::snapshot(&auth_aggregate),
+        );
+        Ok(auth_aggregate)
+    }
+
+    pub async fn get_by_email(&self, email: &str) -> Result<AuthAggregate, ApplicationError> {
+        let id = {
+            let scope = self.executor.read().await.transaction_scope();
+            let mut executor = self.executor.write().await;
+            let query = sqlx::query_scalar!("SELECT id FROM auth_account WHERE email = $1", email);
+            match scope {
+                Some(_) => query.fetch_optional(executor.transaction().await?).await,
+                None => query.fetch_optional(executor.reader()).await,
+            }
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?
+            .ok_or(ApplicationError::EntityNotFound)?
+        };
+        self.get(&id).await
+    }
+
+    /// Account whose session the hash belongs to, either as current refresh token or one rotated out.
+    pub async fn get_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<AuthAggregate, ApplicationError> {
+        let id = {
+            let scope = self.executor.read().await.transaction_scope();
+            let mut executor = self.executor.write().await;
+            let query = sqlx::query_scalar!(
+                "SELECT account_id FROM auth_token_stat
+                WHERE refresh_token_hash = $1 OR $1 = ANY(rotated_refresh_token_hashes)
+                LIMIT 1",
+                refresh_token_hash
+            );
+            match scope {
+                Some(_) => query.fetch_optional(executor.transaction().await?).await,
+                None => query.fetch_optional(executor.reader()).await,
+            }
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?
+            .ok_or(ApplicationError::EntityNotFound)?
+        };
+        self.get(&id).await
+    }
+
+    /// Account whose pending email verification the token hash belongs to.
+    pub async fn get_by_verification_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<AuthAggregate, ApplicationError> {
+        let id = {
+            let scope = self.executor.read().await.transaction_scope();
+            let mut executor = self.executor.write().await;
+            let query = sqlx::query_scalar!(
+                "SELECT account_id FROM auth_email_verification WHERE token_hash = $1",
+                token_hash
+            );
+            match scope {
+                Some(_) => query.fetch_optional(executor.transaction().await?).await,
+                None => query.fetch_optional(executor.reader()).await,
+            }
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?
+            .ok_or(ApplicationError::EntityNotFound)?
+        };
+        self.get(&id).await
+    }
+
+    /// Account whose pending password reset the token hash belongs to.
+    pub async fn get_by_password_reset_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<AuthAggregate, ApplicationError> {
+        let id = {
+            let scope = self.executor.read().await.transaction_scope();
+            let mut executor = self.executor.write().await;
+            let query = sqlx::query_scalar!(
+                "SELECT account_id FROM auth_password_reset WHERE token_hash = $1",
+                token_hash
+            );
+            match scope {
+                Some(_) => query.fetch_optional(executor.transaction().await?).await,
+                None => query.fetch_optional(executor.reader()).await,
+            }
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?
+            .ok_or(ApplicationError::EntityNotFound)?
+        };
+        self.get(&id).await
+    }
+
+    /// Persist only what has changed since the aggregate was loaded or saved through this repository within the transaction.
+    /// Account version is bumped whenever any part of the aggregate has changed.
+    /// Aggregate that is not tracked is taken to have changed every part of it.
+    pub async fn _update(&mut self, aggregate: &mut AuthAggregate) -> Result<(), ApplicationError> {
+        let id = aggregate.account.id.clone();
+        let scope = self.executor.read().await.transaction_scope();
+        // * Sessions tracked are kept, so that only those added or changed are saved.
+        let (
+            account_changed,
+            tracked_sessions,
+            verification_changed,
+            password_reset_changed,
+            totp_changed,
+        ) = match self.identity_map.read().await.get(scope, &id) {
+            Some(tracked) => (
+                tracked.account != aggregate.account,
+                Some(tracked.sessions.clone()),
+                tracked.verification != aggregate.verification,
+                tracked.password_reset != aggregate.password_reset,
+                tracked.totp != aggregate.totp,
+            ),
+            None => (true, None, true, true, true),
+        };
+        let sessions_changed = tracked_sessions.as_ref() != Some(&aggregate.sessions);
+
+        if !account_changed
+            && !sessions_changed
+            && !verification_changed
+            && !password_reset_changed
+            && !totp_changed
+        {
+            return Ok(());
+        }
+
+        let account = &aggregate.account;
+
+        // * Update Account
+        // * Version that doesn't match means the account has been updated since it was loaded.
+        let res = if account_changed {
+            sqlx::query!(
+                "UPDATE auth_account SET
+                email = $1,
+                state = $2,
+                role = $3,
+                hashed_password = $4,
+                nickname = $5,
+                locked_until = $6,
+                version = $7
+                WHERE id = $8 AND version = $9",
+                account.email,
+                account.state.clone() as AccountState,
+                account.role as AccountRole,
+                account.hashed_password,
+                account.nickname,
+                account.locked_until,
+                account.version + 1,
+                account.id,
+                account.version
+            )
+            .execute(self.executor.write().await.transaction().await?)
+            .await
+        } else {
+            sqlx::query!(
+                "UPDATE auth_account SET version = $1 WHERE id = $2 AND version = $3",
+                account.version + 1,
+                account.id,
+                account.version
+            )
+            .execute(self.executor.write().await.transaction().await?)
+            .await
+        }
+        .map_err(Self::conflict_or_error)?;
+        if res.rows_affected() == 0 {
+            eprintln!("Account Has Been Changed Concurrently! Account:{}", account.id);
+            return Err(ApplicationError::ConcurrencyConflict);
+        }
+
+        // * Sessions, of which those no longer in the aggregate are deleted as revoked.
+        if sessions_changed {
+            let session_ids: Vec<Uuid> = aggregate
+                .sessions
+                .iter()
+                .map(|session| session.id)
+                .collect();
+            sqlx::query!(
+                "DELETE FROM auth_token_stat WHERE account_id = $1 AND NOT (id = ANY($2))",
+                account.id,
+                &session_ids
+            )
+            .execute(self.executor.write().await.transaction().await?)
+            .await
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+            let tracked_sessions = tracked_sessions.unwrap_or_default();
+            for session in aggregate.sessions.iter() {
+                if !tracked_sessions.contains(session) {
+                    self.save_session(&account.id, session).await?;
+                }
+            }
+        }
+
+        // * Email Verification, which is deleted once used so that the token is not used again.
+        if verification_changed {
+            match aggregate.verification.as_ref() {
+                Some(verification) => self.save_verification(&account.id, verification).await?,
+                None => {
+                    sqlx::query!(
+                        "DELETE FROM auth_email_verification WHERE account_id = $1",
+                        account.id
+                    )
+                    .execute(self.executor.write().await.transaction().await?)
+                    .await
+                    .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+                }
+            }
+        }
+
+        // * Password Reset, which is deleted once used so that the token is not used again.
+        if password_reset_changed {
+            match aggregate.password_reset.as_ref() {
+                Some(password_reset) => {
+                    self.save_password_reset(&account.id, password_reset).await?
+                }
+                None => {
+                    sqlx::query!(
+                        "DELETE FROM auth_password_reset WHERE account_id = $1",
+                        account.id
+                    )
+                    .execute(self.executor.write().await.transaction().await?)
+                    .await
+                    .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+                }
+            }
+        }
+
+        // * TOTP
+        if totp_changed {
+            match aggregate.totp.as_ref() {
+                Some(totp) => self.save_totp(&account.id, totp).await?,
+                None => {
+                    sqlx::query!("DELETE FROM auth_totp WHERE account_id = $1", account.id)
+                        .execute(self.executor.write().await.transaction().await?)
+                        .await
+                        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+                }
+            }
+        }
+
+        aggregate.account.version += 1;
+        self.identity_map
+            .write()
+            .await
+            .insert(scope, id, Self::snapshot(aggregate));
+        Ok(())
+    }
+
+    async fn save_session(
+        &mut self,
+        account_id: &str,
+        session: &TokenStat,
+    ) -> Result<(), ApplicationError> {
+        sqlx::query!(
+            "INSERT INTO auth_token_stat (id, account_id, refresh_token_hash, rotated_refresh_token_hashes, expiry_date, user_agent, ip, create_dt, last_seen_dt)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO UPDATE SET
+                refresh_token_hash = EXCLUDED.refresh_token_hash,
+                rotated_refresh_token_hashes = EXCLUDED.rotated_refresh_token_hashes,
+                expiry_date = EXCLUDED.expiry_date,
+                last_seen_dt = EXCLUDED.last_seen_dt",
+            session.id,
+            account_id,
+            session.refresh_token_hash,
+            &session.rotated_refresh_token_hashes,
+            session.expiry_date,
+            session.user_agent,
+            session.ip,
+            session.create_dt,
+            session.last_seen_dt,
+        )
+        .execute(self.executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        Ok(())
+    }
+
+    async fn save_verification(
+        &mut self,
+        account_id: &str,
+        verification: &EmailVerification,
+    ) -> Result<(), ApplicationError> {
+        sqlx::query!(
+            "INSERT INTO auth_email_verification (account_id, token_hash, expiry_date, requested_dt)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (account_id) DO UPDATE SET
+                token_hash = EXCLUDED.token_hash,
+                expiry_date = EXCLUDED.expiry_date,
+                requested_dt = EXCLUDED.requested_dt",
+            account_id,
+            verification.token_hash,
+            verification.expiry_date,
+            verification.requested_dt,
+        )
+        .execute(self.executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        Ok(())
+    }
+
+    async fn save_password_reset(
+        &mut self,
+        account_id: &str,
+        password_reset: &PasswordReset,
+    ) -> Result<(), ApplicationError> {
+        sqlx::query!(
+            "INSERT INTO auth_password_reset (account_id, token_hash, expiry_date, requested_dt)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (account_id) DO UPDATE SET
+                token_hash = EXCLUDED.token_hash,
+                expiry_date = EXCLUDED.expiry_date,
+                requested_dt = EXCLUDED.requested_dt",
+            account_id,
+            password_reset.token_hash,
+            password_reset.expiry_date,
+            password_reset.requested_dt,
+        )
+        .execute(self.executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        Ok(())
+    }
 
-    async fn _add(&mut self, _aggregate: &AuthAggregate) -> Result<String, ApplicationError> {
-        unimplemented!()
+    async fn save_totp(
+        &mut self,
+        account_id: &str,
+        totp: &TotpCredential,
+    ) -> Result<(), ApplicationError> {
+        sqlx::query!(
+            "INSERT INTO auth_totp (account_id, encrypted_secret, confirmed, recovery_code_hashes, last_used_step)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (account_id) DO UPDATE SET
+                encrypted_secret = EXCLUDED.encrypted_secret,
+                confirmed = EXCLUDED.confirmed,
+                recovery_code_hashes = EXCLUDED.recovery_code_hashes,
+                last_used_step = EXCLUDED.last_used_step",
+            account_id,
+            totp.encrypted_secret,
+            totp.confirmed,
+            &totp.recovery_code_hashes,
+            totp.last_used_step,
+        )
+        .execute(self.executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        Ok(())
     }
 
-    async fn get(&self, _aggregate_id: &str) -> Result<AuthAggregate, ApplicationError> {
-        unimplemented!()
+    // * Unique violation(23505) on email or nickname means they are taken by another account.
+    fn conflict_or_error(err: sqlx::Error) -> ApplicationError {
+        if let sqlx::Error::Database(db_err) = &err {
+            if db_err.code().as_deref() == Some("23505") {
+                match db_err.constraint() {
+                    Some("auth_account_email_key") => {
+                        return ApplicationError::EntityAlreadyExists("email".into())
+                    }
+                    Some("auth_account_nickname_key") => {
+                        return ApplicationError::EntityAlreadyExists("nickname".into())
+                    }
+                    _ => (),
+                }
+            }
+        }
+        ApplicationError::DatabaseConnectionError(Box::new(err))
     }
 
-    async fn _update(&mut self, _aggregate: &AuthAggregate) -> Result<(), ApplicationError> {
-        unimplemented!()
+    // * Copy of aggregate without events, which is what identity map keeps.
+    fn snapshot(aggregate: &AuthAggregate) -> AuthAggregate {
+        let mut snapshot = AuthAggregate::builder()
+            .take_account(aggregate.account.clone())
+            .build();
+        snapshot.sessions = aggregate.sessions.clone();
+        snapshot.verification = aggregate.verification.clone();
+        snapshot.password_reset = aggregate.password_reset.clone();
+        snapshot.totp = aggregate.totp.clone();
+        snapshot
     }
 }
@@ -3,17 +3,20 @@ use crate::domain::board::entity::{Board, BoardState, Comment, CommentState};
 
 use crate::domain::board::BoardAggregate;
 
-use crate::domain::{builder::*};
+use crate::domain::builder::*;
 
 use crate::utils::ApplicationError;
 
+use std::collections::{HashMap, HashSet};
 use std::mem;
 
 use std::str::FromStr;
 
+use sqlx::PgConnection;
+
 use uuid::Uuid;
 
-use super::{Repository, TRepository};
+use super::Repository;
 
 
 impl Repository<BoardAggregate> {
@@ -38,13 +41,43 @@ impl Repository<BoardAggregate> {
             &board.content,
             &board.tags,
             board.state.clone() as BoardState,
-        ).execute(self.executor.write().await.transaction()).await.map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        ).execute(self.executor.write().await.transaction().await?).await.map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
 
+        // * Comments are not inserted along with board, so they are left untracked.
+        let mut snapshot = Self::snapshot(aggregate);
+        snapshot.comments.clear();
+        let scope = self.executor.read().await.transaction_scope();
+        self.identity_map
+            .write()
+            .await
+            .insert(scope, board.id.to_string(), snapshot);
         Ok(board.id.to_string())
     }
 
+    /// Aggregate already loaded within the transaction is given out of identity map without querying.
     pub async fn get(&self, _aggregate_id: &str) -> Result<BoardAggregate, ApplicationError> {
-        let uuidfied = Uuid::from_str(_aggregate_id).unwrap();
+        let scope = self.executor.read().await.transaction_scope();
+        if let Some(aggregate) = self.identity_map.read().await.get(scope, _aggregate_id) {
+            return Ok(Self::snapshot(aggregate));
+        }
+        // * Id that is not even UUID, as given on request path, cannot belong to any board.
+        let uuidfied =
+            Uuid::from_str(_aggregate_id).map_err(|_| ApplicationError::EntityNotFound)?;
+
+        // * Read within transaction goes to the primary, otherwise to replica.
+        let mut executor = self.executor.write().await;
+        let mut pool_connection;
+        let connection: &mut PgConnection = match scope {
+            Some(_) => executor.transaction().await?,
+            None => {
+                pool_connection = executor
+                    .reader()
+                    .acquire()
+                    .await
+                    .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+                &mut pool_connection
+            }
+        };
 
         let board = sqlx::query_as!(
             Board,
@@ -63,7 +96,7 @@ impl Repository<BoardAggregate> {
             "#,
             uuidfied
         )
-        .fetch_one(self.executor.read().await.connection())
+        .fetch_one(&mut *connection)
         .await
         .map_err(|err| {
             eprintln!("{}", err);
@@ -85,7 +118,7 @@ impl Repository<BoardAggregate> {
             "#,
             uuidfied,
         )
-        .fetch_all(self.executor.read().await.connection())
+        .fetch_all(connection)
         .await
         .map_err(|err| {
             eprintln!("{}", err);
@@ -98,50 +131,118 @@ impl Repository<BoardAggregate> {
             .take_board(board)
             .take_comments(comments)
             .build();
+        self.identity_map
+            .write()
+            .await
+            .insert(scope, _aggregate_id.into(), Self::snapshot(&board_aggregate));
         Ok(board_aggregate)
     }
 
-    pub async fn _update(&mut self, aggregate: &BoardAggregate) -> Result<(), ApplicationError> {
-        let board = &aggregate.board;
+    /// Persist only what has changed since the aggregate was loaded or saved through this repository within the transaction.
+    /// Board version is bumped whenever any part of the aggregate has changed.
+    /// Aggregate that is not tracked is taken to have changed its board, new comments and comments pending update.
+    pub async fn _update(&mut self, aggregate: &mut BoardAggregate) -> Result<(), ApplicationError> {
+        let id = aggregate.board.id.to_string();
+        let scope = self.executor.read().await.transaction_scope();
+        let (board_changed, to_be_added, to_be_updated, to_be_deleted) =
+            match self.identity_map.read().await.get(scope, &id) {
+                Some(tracked) => {
+                    let tracked_comments: HashMap<Uuid, &Comment> =
+                        tracked.comments.iter().map(|c| (c.id, c)).collect();
+                    let current_ids: HashSet<Uuid> =
+                        aggregate.comments.iter().map(|c| c.id).collect();
 
-        let mut to_be_added_comment: Option<&Comment> = None;
-        let mut to_be_updated_comment: Option<&Comment> = None;
+                    let mut to_be_added = vec![];
+                    let mut to_be_updated = vec![];
+                    for comment in aggregate.comments.iter() {
+                        match tracked_comments.get(&comment.id) {
+                            None => to_be_added.push(comment.clone()),
+                            Some(tracked_comment) if *tracked_comment != comment => {
+                                to_be_updated.push(comment.clone())
+                            }
+                            Some(_) => (),
+                        }
+                    }
+                    let to_be_deleted: Vec<Uuid> = tracked_comments
+                        .keys()
+                        .filter(|id| !current_ids.contains(id))
+                        .cloned()
+                        .collect();
+                    (
+                        tracked.board != aggregate.board,
+                        to_be_added,
+                        to_be_updated,
+                        to_be_deleted,
+                    )
+                }
+                None => (
+                    true,
+                    aggregate
+                        .comments
+                        .iter()
+                        .filter(|c| c.state == CommentState::Pending)
+                        .cloned()
+                        .collect(),
+                    aggregate
+                        .comments
+                        .iter()
+                        .filter(|c| c.state == CommentState::UpdatePending)
+                        .cloned()
+                        .collect(),
+                    vec![],
+                ),
+            };
 
-        for comment in aggregate.comments.iter() {
-            if comment.state == CommentState::Pending {
-                to_be_added_comment = Some(comment);
-                break;
-            }
-            if comment.state == CommentState::UpdatePending {
-                to_be_updated_comment = Some(comment);
-                break;
-            }
+        if !board_changed
+            && to_be_added.is_empty()
+            && to_be_updated.is_empty()
+            && to_be_deleted.is_empty()
+        {
+            return Ok(());
         }
 
+        let board = &aggregate.board;
+
         // * Update Board
-        sqlx::query_as!(
-            Board,
-            "UPDATE community_board SET 
-            author = $1,
-            title = $2,
-            content = $3,
-            state = $4,
-            version = $5
-            WHERE id = $6 AND version = $7",
-            board.author,
-            board.title,
-            board.content,
-            board.state.clone() as BoardState,
-            board.version + 1,
-            board.id,
-            board.version
-        )
-        .execute(self.executor.write().await.transaction())
-        .await
+        // * Version that doesn't match means the board has been updated since it was loaded.
+        let res = if board_changed {
+            sqlx::query_as!(
+                Board,
+                "UPDATE community_board SET 
+                author = $1,
+                title = $2,
+                content = $3,
+                state = $4,
+                version = $5
+                WHERE id = $6 AND version = $7",
+                board.author,
+                board.title,
+                board.content,
+                board.state.clone() as BoardState,
+                board.version + 1,
+                board.id,
+                board.version
+            )
+            .execute(self.executor.write().await.transaction().await?)
+            .await
+        } else {
+            sqlx::query!(
+                "UPDATE community_board SET version = $1 WHERE id = $2 AND version = $3",
+                board.version + 1,
+                board.id,
+                board.version
+            )
+            .execute(self.executor.write().await.transaction().await?)
+            .await
+        }
         .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        if res.rows_affected() == 0 {
+            eprintln!("Board Has Been Changed Concurrently! Board:{}", board.id);
+            return Err(ApplicationError::ConcurrencyConflict);
+        }
 
         // * Insert Comment
-        if let Some(comment) = to_be_added_comment {
+        for comment in to_be_added.iter() {
             sqlx::query_as!(
                 Comment,
                 "INSERT INTO community_comment (
@@ -157,28 +258,64 @@ impl Repository<BoardAggregate> {
                 comment.board_id,
                 comment.author,
                 comment.content,
-                CommentState::Created as CommentState,
+                Self::persisted_state(&comment.state) as CommentState,
                 comment.create_dt
             )
-            .execute(self.executor.write().await.transaction())
+            .execute(self.executor.write().await.transaction().await?)
             .await
             .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
         }
 
-        if let Some(comment) = to_be_updated_comment {
+        for comment in to_be_updated.iter() {
             sqlx::query_as!(
                 Comment,
                 r#"UPDATE community_comment SET 
-                    content =$1
-                WHERE id = $2"#,
+                    content = $1,
+                    state = $2
+                WHERE id = $3"#,
                 comment.content,
+                Self::persisted_state(&comment.state) as CommentState,
                 comment.id
             )
-            .execute(self.executor.write().await.transaction())
+            .execute(self.executor.write().await.transaction().await?)
+            .await
+            .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+        }
+
+        if !to_be_deleted.is_empty() {
+            sqlx::query!(
+                "DELETE FROM community_comment WHERE id = ANY($1)",
+                &to_be_deleted
+            )
+            .execute(self.executor.write().await.transaction().await?)
             .await
             .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
         }
 
+        aggregate.board.version += 1;
+        for comment in aggregate.comments.iter_mut() {
+            comment.state = Self::persisted_state(&comment.state);
+        }
+        self.identity_map
+            .write()
+            .await
+            .insert(scope, id, Self::snapshot(aggregate));
         Ok(())
     }
+
+    // * Pending states only live in memory, standing for comments that are yet to be persisted.
+    fn persisted_state(state: &CommentState) -> CommentState {
+        match state {
+            CommentState::Pending | CommentState::UpdatePending => CommentState::Created,
+            state => state.clone(),
+        }
+    }
+
+    // * Copy of aggregate without events, which is what identity map keeps.
+    fn snapshot(aggregate: &BoardAggregate) -> BoardAggregate {
+        BoardAggregate::builder()
+            .take_board(aggregate.board.clone())
+            .take_comments(aggregate.comments.clone())
+            .build()
+    }
 }
\ No newline at end of file
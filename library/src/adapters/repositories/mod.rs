@@ -1,12 +1,12 @@
 pub mod auth_repository;
 pub mod board_repository;
 
-use crate::domain::{Aggregate, Message};
+use crate::domain::{auth::AuthAggregate, board::BoardAggregate, Aggregate, Message};
 
 use async_trait::async_trait;
 use tokio::sync::RwLock;
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::mem;
 use std::sync::Arc;
@@ -19,7 +19,6 @@ pub trait TRepository {
     fn new(executor: Arc<RwLock<Executor>>) -> Self;
 
     fn get_events(&mut self) -> VecDeque<Box<dyn Message>>;
-    fn set_events(&mut self, events: VecDeque<Box<dyn Message>>);
 
 
 }
@@ -28,6 +27,42 @@ pub struct Repository<A: Aggregate> {
     pub executor: Arc<RwLock<Executor>>,
     pub _phantom: PhantomData<A>,
     pub events: VecDeque<Box<dyn Message>>,
+
+    pub(crate) identity_map: RwLock<IdentityMap<A>>,
+}
+
+/// Aggregates as they were last loaded or saved within a transaction, keyed by aggregate id.
+/// Once the transaction is over, what is kept is no longer trusted and the map starts over.
+pub(crate) struct IdentityMap<A> {
+    scope: Option<usize>,
+    aggregates: HashMap<String, A>,
+}
+
+impl<A> Default for IdentityMap<A> {
+    fn default() -> Self {
+        Self {
+            scope: None,
+            aggregates: Default::default(),
+        }
+    }
+}
+
+impl<A> IdentityMap<A> {
+    pub(crate) fn get(&self, scope: Option<usize>, id: &str) -> Option<&A> {
+        match scope {
+            Some(_) if scope == self.scope => self.aggregates.get(id),
+            _ => None,
+        }
+    }
+    pub(crate) fn insert(&mut self, scope: Option<usize>, id: String, aggregate: A) {
+        if scope != self.scope {
+            self.scope = scope;
+            self.aggregates.clear();
+        }
+        if scope.is_some() {
+            self.aggregates.insert(id, aggregate);
+        }
+    }
 }
 
 
@@ -36,9 +71,33 @@ impl<A:Aggregate> TRepository for Repository<A>{
         mem::take(&mut self.events)
     }
     fn new(executor:Arc<RwLock<Executor> >) -> Self {
-        Self { executor, _phantom: PhantomData, events: Default::default() }
+        Self { executor, _phantom: PhantomData, events: Default::default(), identity_map: Default::default() }
     }
-    fn set_events(&mut self,events:VecDeque<Box<dyn Message> >) {
+}
+
+impl<A: Aggregate> Repository<A> {
+    pub fn set_events(&mut self, events: VecDeque<Box<dyn Message>>) {
         self.events = events
     }
+}
+
+/// Repositories of every aggregate over the same executor,
+/// so that a single unit of work can span more than one aggregate.
+pub struct Repositories {
+    pub boards: Repository<BoardAggregate>,
+    pub accounts: Repository<AuthAggregate>,
+}
+
+impl TRepository for Repositories {
+    fn new(executor: Arc<RwLock<Executor>>) -> Self {
+        Self {
+            boards: Repository::new(executor.clone()),
+            accounts: Repository::new(executor),
+        }
+    }
+    fn get_events(&mut self) -> VecDeque<Box<dyn Message>> {
+        let mut events = self.boards.get_events();
+        events.append(&mut self.accounts.get_events());
+        events
+    }
 }
\ No newline at end of file
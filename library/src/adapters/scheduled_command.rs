@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgExecutor;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    domain::commands::Command,
+    utils::{ApplicationError, ApplicationResult},
+};
+
+use super::database::Executor;
+
+#[derive(Eq, PartialEq, Clone, Hash, sqlx::Type, Debug)]
+#[sqlx(type_name = "scheduled_command_state")]
+pub enum ScheduledCommandState {
+    Pending,
+    Processed,
+    Canceled,
+    Failed,
+}
+
+/// Command that is persisted to be dispatched through `MessageBus` once `due_dt` has passed.
+#[derive(Debug, Clone)]
+pub struct ScheduledCommand {
+    pub(crate) id: Uuid,
+    pub(crate) command_type: String,
+    pub(crate) payload: String,
+    pub(crate) due_dt: DateTime<Utc>,
+    pub(crate) dedupe_key: Option<String>,
+    pub(crate) state: ScheduledCommandState,
+    pub(crate) attempts: i32,
+    pub(crate) create_dt: DateTime<Utc>,
+}
+
+impl ScheduledCommand {
+    pub fn new<C>(command: &C, due_dt: DateTime<Utc>) -> ApplicationResult<Self>
+    where
+        C: Command + Serialize,
+    {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            command_type: C::NAME.into(),
+            payload: serde_json::to_string(command)
+                .map_err(|err| ApplicationError::SerializationError(Box::new(err)))?,
+            due_dt,
+            dedupe_key: None,
+            state: ScheduledCommandState::Pending,
+            attempts: 0,
+            create_dt: Utc::now(),
+        })
+    }
+
+    /// Scheduling the same `dedupe_key` more than once leaves only the first one.
+    pub fn with_dedupe_key(mut self, dedupe_key: impl Into<String>) -> Self {
+        self.dedupe_key = Some(dedupe_key.into());
+        self
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+    pub fn state(&self) -> &ScheduledCommandState {
+        &self.state
+    }
+    pub fn command_type(&self) -> &str {
+        &self.command_type
+    }
+
+    /// Persist scheduled command and return its id.
+    /// When the dedupe key is already taken, the id of the previously scheduled command is returned.
+    pub async fn add(&self, executor: Arc<RwLock<Executor>>) -> ApplicationResult<Uuid> {
+        if let Some(id) = self.insert(executor.read().await.connection()).await? {
+            return Ok(id);
+        }
+
+        sqlx::query!(
+            "SELECT id FROM service_scheduled_command WHERE dedupe_key = $1",
+            self.dedupe_key
+        )
+        .fetch_one(executor.read().await.connection())
+        .await
+        .map(|record| record.id)
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
+    }
+
+    /// Insert scheduled command, returning `None` when the dedupe key is already taken.
+    /// As it takes any executor, it can be run within an ongoing transaction.
+    pub(crate) async fn insert<'c>(
+        &self,
+        executor: impl PgExecutor<'c>,
+    ) -> ApplicationResult<Option<Uuid>> {
+        sqlx::query!(
+            "INSERT INTO service_scheduled_command
+            (id, command_type, payload, due_dt, dedupe_key, state, attempts, create_dt) VALUES
+            ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (dedupe_key) DO NOTHING
+            RETURNING id",
+            self.id,
+            self.command_type,
+            self.payload,
+            self.due_dt,
+            self.dedupe_key,
+            self.state.clone() as ScheduledCommandState,
+            self.attempts,
+            self.create_dt,
+        )
+        .fetch_optional(executor)
+        .await
+        .map(|record| record.map(|record| record.id))
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
+    }
+
+    pub async fn get(executor: Arc<RwLock<Executor>>, id: Uuid) -> ApplicationResult<Self> {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                id,
+                command_type,
+                payload,
+                due_dt,
+                dedupe_key,
+                state AS "state: ScheduledCommandState",
+                attempts,
+                create_dt
+            FROM service_scheduled_command
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(executor.read().await.connection())
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?
+        .ok_or(ApplicationError::EntityNotFound)
+    }
+
+    /// Cancel pending command. Command that has already been processed, failed or canceled is not found.
+    pub async fn cancel(executor: Arc<RwLock<Executor>>, id: Uuid) -> ApplicationResult<()> {
+        let res = sqlx::query!(
+            "UPDATE service_scheduled_command SET state = $1 WHERE id = $2 AND state = $3",
+            ScheduledCommandState::Canceled as ScheduledCommandState,
+            id,
+            ScheduledCommandState::Pending as ScheduledCommandState,
+        )
+        .execute(executor.read().await.connection())
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))?;
+
+        if res.rows_affected() == 0 {
+            return Err(ApplicationError::EntityNotFound);
+        }
+        Ok(())
+    }
+
+    /// Ids of pending commands that are due, in the order they fell due.
+    pub async fn due_ids(
+        executor: Arc<RwLock<Executor>>,
+        limit: i64,
+    ) -> ApplicationResult<Vec<Uuid>> {
+        sqlx::query_scalar!(
+            "SELECT id FROM service_scheduled_command
+            WHERE state = $1 AND due_dt <= NOW()
+            ORDER BY due_dt
+            LIMIT $2",
+            ScheduledCommandState::Pending as ScheduledCommandState,
+            limit
+        )
+        .fetch_all(executor.read().await.connection())
+        .await
+        .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
+    }
+
+    /// Lock pending command within the executor's transaction so that concurrent workers skip over it while it is dispatched.
+    /// Command that is locked by another worker or no longer pending is `None`.
+    pub async fn claim(
+        executor: Arc<RwLock<Executor>>,
+        id: Uuid,
+    ) -> ApplicationResult<Option<Self>> {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                id,
+                command_type,
+                payload,
+                due_dt,
+                dedupe_key,
+                state AS "state: ScheduledCommandState",
+                attempts,
+                create_dt
+            FROM service_scheduled_command
+            WHERE id = $1 AND state = $2
+            FOR UPDATE SKIP LOCKED
+            "#,
+            id,
+            ScheduledCommandState::Pending as ScheduledCommandState,
+        )
+        .fetch_optional(executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })
+    }
+
+    /// Key the command is dispatched with, so that it is handled only once however many times it is dispatched.
+    pub fn idempotency_key(&self) -> String {
+        format!("scheduled-command-{}", self.id)
+    }
+
+    pub fn tag_processed(&mut self) {
+        self.state = ScheduledCommandState::Processed
+    }
+
+    /// Record failed dispatch. Once `max_attempts` is reached, the command is given up on.
+    pub fn tag_failed_attempt(&mut self, max_attempts: i32) {
+        self.attempts += 1;
+        if self.attempts >= max_attempts {
+            self.state = ScheduledCommandState::Failed
+        }
+    }
+
+    pub async fn update(&self, executor: Arc<RwLock<Executor>>) -> ApplicationResult<()> {
+        sqlx::query!(
+            "UPDATE service_scheduled_command SET state = $1, attempts = $2 WHERE id = $3",
+            self.state.clone() as ScheduledCommandState,
+            self.attempts,
+            self.id,
+        )
+        .execute(executor.write().await.transaction().await?)
+        .await
+        .map_err(|err| {
+            eprintln!("{}", err);
+            ApplicationError::DatabaseConnectionError(Box::new(err))
+        })?;
+        Ok(())
+    }
+}
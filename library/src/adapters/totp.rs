@@ -0,0 +1,186 @@
+use std::sync::Arc;
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use chrono::{DateTime, Utc};
+use data_encoding::{BASE32_NOPAD, BASE64};
+use hmac::{digest::core_api::BlockSizeUser, Mac, SimpleHmac};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use rand::RngCore;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{
+    config::AuthConfig,
+    utils::{ApplicationError, ApplicationResult},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Time-based one-time password as in RFC 6238, which is HOTP(RFC 4226) over the number of time steps since Unix epoch.
+pub struct Totp {
+    secret: Vec<u8>,
+    algorithm: TotpAlgorithm,
+    digits: u32,
+    step_secs: i64,
+}
+
+impl Totp {
+    // * Length recommended by RFC 4226 for HMAC-SHA1.
+    const SECRET_LEN: usize = 20;
+
+    /// TOTP with the parameters authenticator apps assume, which are SHA-1, 6 digits and 30 seconds step.
+    pub fn new(secret: Vec<u8>) -> Self {
+        Self::with_params(secret, TotpAlgorithm::Sha1, 6, 30)
+    }
+
+    pub fn with_params(
+        secret: Vec<u8>,
+        algorithm: TotpAlgorithm,
+        digits: u32,
+        step_secs: i64,
+    ) -> Self {
+        Self {
+            secret,
+            algorithm,
+            digits,
+            step_secs,
+        }
+    }
+
+    /// TOTP with the secret given in base32, as entered into authenticator apps.
+    pub fn from_encoded_secret(encoded_secret: &str) -> Option<Self> {
+        BASE32_NOPAD
+            .decode(encoded_secret.as_bytes())
+            .ok()
+            .map(Self::new)
+    }
+
+    pub fn generate_secret() -> Vec<u8> {
+        let mut secret = vec![0; Self::SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut secret);
+        secret
+    }
+
+    pub fn step(&self, time: DateTime<Utc>) -> i64 {
+        time.timestamp().div_euclid(self.step_secs)
+    }
+
+    pub fn generate(&self, time: DateTime<Utc>) -> String {
+        self.code_at(self.step(time))
+    }
+
+    /// Time step the code given matches, allowing one step of clock drift either way.
+    pub fn verify(&self, code: &str, time: DateTime<Utc>) -> Option<i64> {
+        let step = self.step(time);
+        [step, step - 1, step + 1]
+            .into_iter()
+            .find(|step| *step >= 0 && self.code_at(*step) == code)
+    }
+
+    /// Secret in base32, which is how it is entered into authenticator apps.
+    pub fn encoded_secret(&self) -> String {
+        BASE32_NOPAD.encode(&self.secret)
+    }
+
+    /// `otpauth://` URI for authenticator apps, usually rendered as QR code.
+    pub fn provisioning_uri(&self, issuer: &str, account_name: &str) -> String {
+        let issuer = utf8_percent_encode(issuer, NON_ALPHANUMERIC);
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm={}&digits={}&period={}",
+            issuer,
+            utf8_percent_encode(account_name, NON_ALPHANUMERIC),
+            self.encoded_secret(),
+            issuer,
+            match self.algorithm {
+                TotpAlgorithm::Sha1 => "SHA1",
+                TotpAlgorithm::Sha256 => "SHA256",
+                TotpAlgorithm::Sha512 => "SHA512",
+            },
+            self.digits,
+            self.step_secs
+        )
+    }
+
+    // * Dynamic truncation of RFC 4226, section 5.3.
+    fn code_at(&self, step: i64) -> String {
+        let counter = (step as u64).to_be_bytes();
+        let hash = match self.algorithm {
+            TotpAlgorithm::Sha1 => hmac::<Sha1>(&self.secret, &counter),
+            TotpAlgorithm::Sha256 => hmac::<Sha256>(&self.secret, &counter),
+            TotpAlgorithm::Sha512 => hmac::<Sha512>(&self.secret, &counter),
+        };
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary as u64 % 10u64.pow(self.digits),
+            width = self.digits as usize
+        )
+    }
+}
+
+fn hmac<D: Digest + BlockSizeUser>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = <SimpleHmac<D> as Mac>::new_from_slice(key).expect("HMAC takes key of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Encrypts secrets kept at rest, such as TOTP secret that must be read back to check codes.
+/// It is AES-256-GCM keyed with SHA-256 of the key configured, and ciphertext is kept in base64 after its nonce.
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &str) -> Self {
+        let key = Sha256::digest(key.as_bytes());
+        Self {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    pub fn encrypt(&self, secret: &[u8]) -> ApplicationResult<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, secret)
+            .map_err(|err| ApplicationError::SecretEncryptionError(err.to_string()))?;
+        Ok(BASE64.encode(&[nonce.as_slice(), &ciphertext].concat()))
+    }
+
+    /// Ciphertext encrypted with other key, or tampered with, is rejected.
+    pub fn decrypt(&self, encrypted: &str) -> ApplicationResult<Vec<u8>> {
+        let decoded = BASE64
+            .decode(encrypted.as_bytes())
+            .map_err(|err| ApplicationError::SecretEncryptionError(err.to_string()))?;
+        // * Nonce of AES-GCM is 96 bits.
+        if decoded.len() < 12 {
+            return Err(ApplicationError::SecretEncryptionError(
+                "ciphertext too short".into(),
+            ));
+        }
+        let (nonce, ciphertext) = decoded.split_at(12);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|err| ApplicationError::SecretEncryptionError(err.to_string()))
+    }
+}
+
+/// Cipher configured under `auth`.
+pub fn secret_cipher(auth: &AuthConfig) -> Arc<SecretCipher> {
+    Arc::new(SecretCipher::new(&auth.totp_encryption_key))
+}
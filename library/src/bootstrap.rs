@@ -1,27 +1,61 @@
 use std::{
     any::{Any, TypeId},
     collections::HashMap,
-    env,
-    sync::OnceLock,
+    sync::{Arc, OnceLock},
 };
 
 use sqlx::{postgres::PgPoolOptions, PgPool};
 
 use crate::{
-    adapters::{database::AtomicContextManager, outbox::Outbox},
+    adapters::{
+        database::{AtomicContextManager, EventChannelConfig},
+        mailer::{self, Mailer},
+        outbox::Outbox,
+        password::{self, PasswordHasher},
+        totp::{self, SecretCipher},
+    },
     domain::{
-        board::{commands::*, events::BoardCreated},
+        auth::commands::{
+            ChangePassword, ConfirmTotp, DisableTotp, EnableTotp, ListSessions, Login,
+            RefreshToken, RegisterAccount, RequestPasswordReset, RequestVerification,
+            ResetPassword, RevokeAllSessions, RevokeSession, UnblockAccount, VerifyEmail,
+            VerifyMfa,
+        },
+        board::{
+            commands::*,
+            events::{BoardCreated, BoardModerated},
+        },
         commands::ServiceResponse,
         Message,
     },
     services::handlers::{self, Future, ServiceHandler},
 };
-use crate::{services::messagebus::MessageBus, utils::ApplicationError};
+use crate::{
+    config::{Config, DatabaseConfig},
+    services::messagebus::MessageBus,
+    utils::{ApplicationError, ApplicationResult},
+};
 
 pub struct Boostrap;
 impl Boostrap {
+    /// Set configuration the application runs on, which must be done before anything is bootstrapped.
+    pub fn configure(config: Config) -> ApplicationResult<()> {
+        config.validate()?;
+        CONFIG.set(config).map_err(|_| {
+            ApplicationError::InvalidConfig("configuration has already been set".into())
+        })
+    }
     pub async fn message_bus() -> std::sync::Arc<MessageBus> {
-        MessageBus::new(command_handler().await, event_handler().await)
+        Self::message_bus_with_channel_config(config().event_channel).await
+    }
+    pub async fn message_bus_with_channel_config(
+        channel_config: EventChannelConfig,
+    ) -> std::sync::Arc<MessageBus> {
+        MessageBus::new(
+            command_handler().await,
+            event_handler().await,
+            channel_config,
+        )
     }
 }
 
@@ -43,13 +77,49 @@ impl Dependency {
             }
         }
     }
+
+    /// Returns whether given title and content pass moderation.
+    pub fn moderation_check(&self) -> fn(&str, &str) -> bool {
+        |title: &str, content: &str| -> bool {
+            const BANNED_WORDS: [&str; 2] = ["spam", "scam"];
+            let (title, content) = (title.to_lowercase(), content.to_lowercase());
+            !BANNED_WORDS
+                .iter()
+                .any(|word| title.contains(word) || content.contains(word))
+        }
+    }
+
+    /// Hasher configured under `auth`, which new passwords are hashed with.
+    pub fn password_hasher(&self) -> Arc<dyn PasswordHasher> {
+        password_hasher().clone()
+    }
+
+    /// Mailer configured under `mail`.
+    pub fn mailer(&self) -> Arc<dyn Mailer> {
+        mailer().clone()
+    }
+
+    /// Cipher configured under `auth`, which TOTP secrets are encrypted with.
+    pub fn secret_cipher(&self) -> Arc<SecretCipher> {
+        secret_cipher().clone()
+    }
 }
 
-pub type EventHandler<T> =
-    HashMap<String, Vec<Box<dyn Fn(Box<dyn Message>, T) -> Future<ServiceResponse> + Send + Sync>>>;
+///* Each handler is registered together with its name so that failures, panics in particular,
+///* can be reported against the handler that caused them.
+pub type EventHandler<T> = HashMap<
+    String,
+    Vec<(
+        &'static str,
+        Box<dyn Fn(Box<dyn Message>, T) -> Future<ServiceResponse> + Send + Sync>,
+    )>,
+>;
 pub type CommandHandler<T> = HashMap<
     TypeId,
-    Box<dyn Fn(Box<dyn Any + Send + Sync>, T) -> Future<ServiceResponse> + Send + Sync>,
+    (
+        &'static str,
+        Box<dyn Fn(Box<dyn Any + Send + Sync>, T) -> Future<ServiceResponse> + Send + Sync>,
+    ),
 >;
 
 macro_rules! init_command_handler {
@@ -57,13 +127,15 @@ macro_rules! init_command_handler {
         {$($command:ty:$handler:expr $(=>($($injectable:ident),*))? ),* }
     )
         => {
-        pub async fn init_command_handler() -> HashMap::<TypeId,Box<dyn Fn(Box<dyn Any + Send + Sync>, AtomicContextManager) -> Future<ServiceResponse> + Send + Sync>>{
-            let _dependency= dependency().await;
+        pub async fn init_command_handler() -> CommandHandler<AtomicContextManager>{
+            let dependency= dependency().await;
 
-            let mut map: HashMap::<_,Box<dyn Fn(_, _ ) -> Future<_> + Send + Sync>> = HashMap::new();
+            let mut map: CommandHandler<AtomicContextManager> = HashMap::new();
             $(
                 map.insert(
                     TypeId::of::<$command>(),
+                    (
+                    stringify!($handler),
                     Box::new(
                         |c:Box<dyn Any+Send+Sync>, context_manager: AtomicContextManager|->Future<ServiceResponse>{
                             // * Convert event so event handler accepts not Box<dyn Message> but `event_happend` type of message.
@@ -78,6 +150,7 @@ macro_rules! init_command_handler {
                           )
                         },
                     )
+                    )
                 );
             )*
             map
@@ -88,14 +161,16 @@ macro_rules! init_event_handler {
     (
         {$($event:ty: [$($handler:expr $(=>($($injectable:ident),*))? ),* ]),*}
     ) =>{
-        pub async fn init_event_handler() -> HashMap<String, Vec<Box<dyn Fn(Box<dyn Message>, AtomicContextManager) -> Future<ServiceResponse> + Send + Sync>>>{
+        pub async fn init_event_handler() -> EventHandler<AtomicContextManager>{
             let dependency= dependency().await;
-            let mut map : HashMap<String, Vec<Box<dyn Fn(_, _) -> Future<_> + Send + Sync>>> = HashMap::new();
+            let mut map : EventHandler<AtomicContextManager> = HashMap::new();
             $(
                 map.insert(
                     stringify!($event).into(),
                     vec![
                         $(
+                            (
+                            stringify!($handler),
                             Box::new(
                                 |e:Box<dyn Message>, context_manager:AtomicContextManager| -> Future<ServiceResponse>{
                                     $handler(
@@ -110,6 +185,7 @@ macro_rules! init_event_handler {
                                     )
                                 }
                                 ),
+                            ),
                         )*
                     ]
                 );
@@ -128,7 +204,25 @@ init_command_handler!(
         EditBoard: ServiceHandler::edit_board,
         AddComment: ServiceHandler::add_comment,
         EditComment: ServiceHandler::edit_comment,
-        Outbox: ServiceHandler::handle_outbox
+        CheckBoardModeration: ServiceHandler::check_board_moderation=>(moderation_check),
+        TakeDownBoard: ServiceHandler::take_down_board,
+        RegisterAccount: ServiceHandler::register_account=>(password_hasher),
+        Login: ServiceHandler::login=>(password_hasher),
+        RefreshToken: ServiceHandler::refresh_token,
+        VerifyEmail: ServiceHandler::verify_email,
+        RequestVerification: ServiceHandler::request_verification,
+        RequestPasswordReset: ServiceHandler::request_password_reset,
+        ResetPassword: ServiceHandler::reset_password=>(password_hasher),
+        ChangePassword: ServiceHandler::change_password=>(password_hasher),
+        UnblockAccount: ServiceHandler::unblock_account,
+        VerifyMfa: ServiceHandler::verify_mfa=>(secret_cipher),
+        EnableTotp: ServiceHandler::enable_totp=>(secret_cipher),
+        ConfirmTotp: ServiceHandler::confirm_totp=>(secret_cipher),
+        DisableTotp: ServiceHandler::disable_totp=>(secret_cipher),
+        ListSessions: ServiceHandler::list_sessions,
+        RevokeSession: ServiceHandler::revoke_session,
+        RevokeAllSessions: ServiceHandler::revoke_all_sessions,
+        Outbox: ServiceHandler::handle_outbox=>(mailer)
     }
 );
 
@@ -136,7 +230,11 @@ init_event_handler!(
     {
         BoardCreated : [
             handlers::EventHandler::test_event_handler=>(some_dependency),
-            handlers::EventHandler::test_event_handler2
+            handlers::EventHandler::test_event_handler2,
+            handlers::EventHandler::start_board_moderation
+        ],
+        BoardModerated : [
+            handlers::EventHandler::advance_board_moderation
         ]
     }
 );
@@ -169,15 +267,31 @@ pub async fn event_handler() -> &'static EventHandler<AtomicContextManager> {
     eh
 }
 
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Configuration the application runs on, which must have been given through `Boostrap::configure`.
+pub fn config() -> &'static Config {
+    CONFIG
+        .get()
+        .expect("Configuration has not been set! Call Boostrap::configure first.")
+}
+
+fn pool_options(config: &DatabaseConfig) -> PgPoolOptions {
+    PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout())
+        .idle_timeout(config.idle_timeout())
+}
+
 static POOL: OnceLock<PgPool> = OnceLock::new();
 
 pub async fn connection_pool() -> &'static PgPool {
-    let url = &env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     let p = match POOL.get() {
         None => {
-            let pool = PgPoolOptions::new()
-                .max_connections(30)
-                .connect(url)
+            let database = &config().database;
+            let pool = pool_options(database)
+                .connect(&database.url)
                 .await
                 .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
                 .unwrap();
@@ -188,6 +302,49 @@ pub async fn connection_pool() -> &'static PgPool {
     p
 }
 
+static REPLICA_POOLS: OnceLock<Vec<PgPool>> = OnceLock::new();
+
+/// Pools of read replicas, which is empty when none is configured.
+pub async fn replica_pools() -> &'static [PgPool] {
+    let p = match REPLICA_POOLS.get() {
+        None => {
+            let database = &config().database;
+            let mut pools = vec![];
+            for url in database.replica_urls.iter() {
+                let pool = pool_options(database)
+                    .connect(url)
+                    .await
+                    .map_err(|err| ApplicationError::DatabaseConnectionError(Box::new(err)))
+                    .unwrap();
+                pools.push(pool);
+            }
+            REPLICA_POOLS.get_or_init(|| pools)
+        }
+        Some(pools) => pools,
+    };
+    p
+}
+
+static PASSWORD_HASHER: OnceLock<Arc<dyn PasswordHasher>> = OnceLock::new();
+
+fn password_hasher() -> &'static Arc<dyn PasswordHasher> {
+    PASSWORD_HASHER.get_or_init(|| {
+        password::password_hasher(&config().auth).unwrap_or_else(|err| panic!("{}", err))
+    })
+}
+
+static MAILER: OnceLock<Arc<dyn Mailer>> = OnceLock::new();
+
+fn mailer() -> &'static Arc<dyn Mailer> {
+    MAILER.get_or_init(|| mailer::mailer(&config().mail))
+}
+
+static SECRET_CIPHER: OnceLock<Arc<SecretCipher>> = OnceLock::new();
+
+fn secret_cipher() -> &'static Arc<SecretCipher> {
+    SECRET_CIPHER.get_or_init(|| totp::secret_cipher(&config().auth))
+}
+
 static DEPENDENCY: OnceLock<Dependency> = OnceLock::new();
 
 pub async fn dependency() -> &'static Dependency {
@@ -0,0 +1,564 @@
+use std::{env, fs, net::SocketAddr, str::FromStr, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    adapters::{
+        database::EventChannelConfig,
+        mailer::MailTransport,
+        migration::MigrationMode,
+        password::{Argon2Hasher, PasswordHasherKind},
+    },
+    utils::{ApplicationError, ApplicationResult},
+};
+
+/// Application configuration.
+/// It is read from environment variables first, over which the TOML file at `CONFIG_FILE`, if set, takes precedence.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub server: ServerConfig,
+    pub relay: RelayConfig,
+    pub event_channel: EventChannelConfig,
+    pub auth: AuthConfig,
+    pub mail: MailConfig,
+    pub features: FeatureConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub replica_urls: Vec<String>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    /// Statement timeout for transactions that do not set their own, where 0 means no timeout.
+    pub statement_timeout_millis: u64,
+    pub migration_mode: MigrationMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub domain: String,
+    pub allow_origins: Vec<String>,
+}
+
+/// Settings for the worker that relays due scheduled commands and process timeouts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RelayConfig {
+    pub interval_millis: u64,
+    pub batch_size: i64,
+    pub max_attempts: i32,
+}
+
+/// Settings for tokens issued on login, password hashing and second factor.
+/// Access token is JWT signed with `jwt_secret`, and TOTP secrets are encrypted with `totp_encryption_key`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    pub access_token_ttl_secs: u64,
+    pub refresh_token_ttl_secs: u64,
+    pub verification_token_ttl_secs: u64,
+    pub verification_request_interval_secs: u64,
+    pub password_reset_token_ttl_secs: u64,
+    pub password_reset_interval_secs: u64,
+    pub lockout_max_failures: i64,
+    pub lockout_window_secs: u64,
+    pub lockout_duration_secs: u64,
+    pub totp_encryption_key: String,
+    pub totp_issuer: String,
+    pub mfa_token_ttl_secs: u64,
+    pub password_hasher: PasswordHasherKind,
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+    pub bcrypt_cost: u32,
+}
+
+/// Settings for mails sent out, such as the one carrying email verification token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    pub dir: String,
+    pub from: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureConfig {
+    pub scheduler: bool,
+    pub swagger: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: Default::default(),
+            replica_urls: vec![],
+            max_connections: 30,
+            min_connections: 0,
+            acquire_timeout_secs: 30,
+            idle_timeout_secs: 600,
+            statement_timeout_millis: 30 * 1000,
+            migration_mode: Default::default(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            domain: "127.0.0.1:3000".into(),
+            allow_origins: vec![],
+        }
+    }
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            interval_millis: 1000,
+            batch_size: 50,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: Default::default(),
+            access_token_ttl_secs: 15 * 60,
+            refresh_token_ttl_secs: 14 * 24 * 60 * 60,
+            verification_token_ttl_secs: 24 * 60 * 60,
+            verification_request_interval_secs: 60,
+            password_reset_token_ttl_secs: 60 * 60,
+            password_reset_interval_secs: 60,
+            lockout_max_failures: 5,
+            lockout_window_secs: 15 * 60,
+            lockout_duration_secs: 15 * 60,
+            totp_encryption_key: Default::default(),
+            totp_issuer: "rustiful".into(),
+            mfa_token_ttl_secs: 5 * 60,
+            // * Minimum recommended by OWASP for Argon2id.
+            password_hasher: Default::default(),
+            argon2_memory_kib: 19 * 1024,
+            argon2_iterations: 2,
+            argon2_parallelism: 1,
+            bcrypt_cost: 12,
+        }
+    }
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            transport: Default::default(),
+            dir: "mails".into(),
+            from: "no-reply@localhost".into(),
+        }
+    }
+}
+
+impl Default for FeatureConfig {
+    fn default() -> Self {
+        Self {
+            scheduler: true,
+            swagger: true,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout_secs)
+    }
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout_secs)
+    }
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        (self.statement_timeout_millis > 0)
+            .then(|| Duration::from_millis(self.statement_timeout_millis))
+    }
+}
+
+impl RelayConfig {
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_millis)
+    }
+}
+
+impl AuthConfig {
+    pub fn access_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.access_token_ttl_secs as i64)
+    }
+    pub fn refresh_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.refresh_token_ttl_secs as i64)
+    }
+    pub fn verification_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.verification_token_ttl_secs as i64)
+    }
+    pub fn verification_request_interval(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.verification_request_interval_secs as i64)
+    }
+    pub fn password_reset_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.password_reset_token_ttl_secs as i64)
+    }
+    pub fn password_reset_interval(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.password_reset_interval_secs as i64)
+    }
+    pub fn lockout_window(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.lockout_window_secs as i64)
+    }
+    pub fn lockout_duration(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.lockout_duration_secs as i64)
+    }
+    pub fn mfa_token_ttl(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.mfa_token_ttl_secs as i64)
+    }
+}
+
+impl Config {
+    /// Load configuration from environment and `CONFIG_FILE`, and validate it.
+    pub fn load() -> ApplicationResult<Self> {
+        let mut config = Self::from_lookup(|key| env::var(key).ok())?;
+        if let Ok(path) = env::var("CONFIG_FILE") {
+            let file = fs::read_to_string(&path).map_err(|err| {
+                ApplicationError::InvalidConfig(format!("CONFIG_FILE({}): {}", path, err))
+            })?;
+            config = config.merge_toml(&file)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read configuration out of environment variables given by `lookup`, leaving unset ones to default.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> ApplicationResult<Self> {
+        let mut config = Self::default();
+        let database = &mut config.database;
+        set_var(&lookup, "DATABASE_URL", &mut database.url)?;
+        set_list(&lookup, "DATABASE_REPLICA_URLS", &mut database.replica_urls);
+        set_var(
+            &lookup,
+            "DATABASE_MAX_CONNECTIONS",
+            &mut database.max_connections,
+        )?;
+        set_var(
+            &lookup,
+            "DATABASE_MIN_CONNECTIONS",
+            &mut database.min_connections,
+        )?;
+        set_var(
+            &lookup,
+            "DATABASE_ACQUIRE_TIMEOUT_SECS",
+            &mut database.acquire_timeout_secs,
+        )?;
+        set_var(
+            &lookup,
+            "DATABASE_IDLE_TIMEOUT_SECS",
+            &mut database.idle_timeout_secs,
+        )?;
+        set_var(
+            &lookup,
+            "DATABASE_STATEMENT_TIMEOUT_MILLIS",
+            &mut database.statement_timeout_millis,
+        )?;
+        set_var(
+            &lookup,
+            "DATABASE_MIGRATION_MODE",
+            &mut database.migration_mode,
+        )?;
+
+        let server = &mut config.server;
+        set_var(&lookup, "DOMAIN", &mut server.domain)?;
+        set_list(&lookup, "ALLOW_ORIGINS", &mut server.allow_origins);
+
+        let relay = &mut config.relay;
+        set_var(&lookup, "RELAY_INTERVAL_MILLIS", &mut relay.interval_millis)?;
+        set_var(&lookup, "RELAY_BATCH_SIZE", &mut relay.batch_size)?;
+        set_var(&lookup, "RELAY_MAX_ATTEMPTS", &mut relay.max_attempts)?;
+
+        let event_channel = &mut config.event_channel;
+        set_var(
+            &lookup,
+            "EVENT_CHANNEL_CAPACITY",
+            &mut event_channel.capacity,
+        )?;
+        set_var(&lookup, "EVENT_CHANNEL_POLICY", &mut event_channel.policy)?;
+
+        let auth = &mut config.auth;
+        set_var(&lookup, "AUTH_JWT_SECRET", &mut auth.jwt_secret)?;
+        set_var(
+            &lookup,
+            "AUTH_ACCESS_TOKEN_TTL_SECS",
+            &mut auth.access_token_ttl_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_REFRESH_TOKEN_TTL_SECS",
+            &mut auth.refresh_token_ttl_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_VERIFICATION_TOKEN_TTL_SECS",
+            &mut auth.verification_token_ttl_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_VERIFICATION_REQUEST_INTERVAL_SECS",
+            &mut auth.verification_request_interval_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_PASSWORD_RESET_TOKEN_TTL_SECS",
+            &mut auth.password_reset_token_ttl_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_PASSWORD_RESET_INTERVAL_SECS",
+            &mut auth.password_reset_interval_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_LOCKOUT_MAX_FAILURES",
+            &mut auth.lockout_max_failures,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_LOCKOUT_WINDOW_SECS",
+            &mut auth.lockout_window_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_LOCKOUT_DURATION_SECS",
+            &mut auth.lockout_duration_secs,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_TOTP_ENCRYPTION_KEY",
+            &mut auth.totp_encryption_key,
+        )?;
+        set_var(&lookup, "AUTH_TOTP_ISSUER", &mut auth.totp_issuer)?;
+        set_var(
+            &lookup,
+            "AUTH_MFA_TOKEN_TTL_SECS",
+            &mut auth.mfa_token_ttl_secs,
+        )?;
+        set_var(&lookup, "AUTH_PASSWORD_HASHER", &mut auth.password_hasher)?;
+        set_var(
+            &lookup,
+            "AUTH_ARGON2_MEMORY_KIB",
+            &mut auth.argon2_memory_kib,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_ARGON2_ITERATIONS",
+            &mut auth.argon2_iterations,
+        )?;
+        set_var(
+            &lookup,
+            "AUTH_ARGON2_PARALLELISM",
+            &mut auth.argon2_parallelism,
+        )?;
+        set_var(&lookup, "AUTH_BCRYPT_COST", &mut auth.bcrypt_cost)?;
+
+        let mail = &mut config.mail;
+        set_var(&lookup, "MAIL_TRANSPORT", &mut mail.transport)?;
+        set_var(&lookup, "MAIL_DIR", &mut mail.dir)?;
+        set_var(&lookup, "MAIL_FROM", &mut mail.from)?;
+
+        let features = &mut config.features;
+        set_var(&lookup, "FEATURE_SCHEDULER", &mut features.scheduler)?;
+        set_var(&lookup, "FEATURE_SWAGGER", &mut features.swagger)?;
+        Ok(config)
+    }
+
+    /// Override configuration with what is given in TOML. Keys left out keep their current value.
+    pub fn merge_toml(self, toml_str: &str) -> ApplicationResult<Self> {
+        let overrides: toml::Table = toml::from_str(toml_str)
+            .map_err(|err| ApplicationError::InvalidConfig(format!("CONFIG_FILE: {}", err)))?;
+        let toml::Value::Table(mut base) = toml::Value::try_from(&self)
+            .map_err(|err| ApplicationError::InvalidConfig(err.to_string()))?
+        else {
+            unreachable!("Config is serialized as table")
+        };
+        merge_table(&mut base, overrides);
+        toml::Value::Table(base)
+            .try_into()
+            .map_err(|err| ApplicationError::InvalidConfig(format!("CONFIG_FILE: {}", err)))
+    }
+
+    /// Check every setting, reporting all that are wrong at once.
+    pub fn validate(&self) -> ApplicationResult<()> {
+        let mut problems = vec![];
+
+        let database = &self.database;
+        for url in std::iter::once(&database.url).chain(database.replica_urls.iter()) {
+            if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) {
+                problems.push(format!(
+                    "database url must start with postgres:// but got {:?}",
+                    url
+                ));
+            }
+        }
+        if database.max_connections == 0 {
+            problems.push("database.max_connections must be greater than 0".into());
+        }
+        if database.min_connections > database.max_connections {
+            problems.push(format!(
+                "database.min_connections({}) must not exceed database.max_connections({})",
+                database.min_connections, database.max_connections
+            ));
+        }
+
+        if SocketAddr::from_str(&self.server.domain).is_err() {
+            problems.push(format!(
+                "server.domain must be socket address such as 127.0.0.1:3000 but got {:?}",
+                self.server.domain
+            ));
+        }
+        for origin in self.server.allow_origins.iter() {
+            if !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                problems.push(format!(
+                    "server.allow_origins must start with http:// or https:// but got {:?}",
+                    origin
+                ));
+            }
+        }
+
+        if self.relay.interval_millis == 0 {
+            problems.push("relay.interval_millis must be greater than 0".into());
+        }
+        if self.relay.batch_size <= 0 {
+            problems.push("relay.batch_size must be greater than 0".into());
+        }
+        if self.relay.max_attempts <= 0 {
+            problems.push("relay.max_attempts must be greater than 0".into());
+        }
+
+        if self.event_channel.capacity == 0 {
+            problems.push("event_channel.capacity must be greater than 0".into());
+        }
+
+        // * HS256 key shorter than its output weakens the signature.
+        if self.auth.jwt_secret.len() < 32 {
+            problems.push("auth.jwt_secret must be at least 32 bytes".into());
+        }
+        if self.auth.access_token_ttl_secs == 0 {
+            problems.push("auth.access_token_ttl_secs must be greater than 0".into());
+        }
+        if self.auth.refresh_token_ttl_secs <= self.auth.access_token_ttl_secs {
+            problems.push(
+                "auth.refresh_token_ttl_secs must be greater than auth.access_token_ttl_secs"
+                    .into(),
+            );
+        }
+
+        if self.auth.verification_token_ttl_secs == 0 {
+            problems.push("auth.verification_token_ttl_secs must be greater than 0".into());
+        }
+        if self.auth.password_reset_token_ttl_secs == 0 {
+            problems.push("auth.password_reset_token_ttl_secs must be greater than 0".into());
+        }
+        if self.auth.lockout_max_failures <= 0 {
+            problems.push("auth.lockout_max_failures must be greater than 0".into());
+        }
+        if self.auth.lockout_window_secs == 0 || self.auth.lockout_duration_secs == 0 {
+            problems.push(
+                "auth.lockout_window_secs and auth.lockout_duration_secs must be greater than 0"
+                    .into(),
+            );
+        }
+        if self.auth.totp_encryption_key.len() < 32 {
+            problems.push("auth.totp_encryption_key must be at least 32 bytes".into());
+        }
+        if self.auth.totp_encryption_key == self.auth.jwt_secret {
+            problems.push("auth.totp_encryption_key must differ from auth.jwt_secret".into());
+        }
+        if self.auth.totp_issuer.is_empty() {
+            problems.push("auth.totp_issuer must not be empty".into());
+        }
+        if self.auth.mfa_token_ttl_secs == 0 {
+            problems.push("auth.mfa_token_ttl_secs must be greater than 0".into());
+        }
+
+        if let Err(ApplicationError::InvalidConfig(problem)) = Argon2Hasher::new(
+            self.auth.argon2_memory_kib,
+            self.auth.argon2_iterations,
+            self.auth.argon2_parallelism,
+        ) {
+            problems.push(format!("auth.{}", problem));
+        }
+        if !(bcrypt::DEFAULT_COST - 2..=31).contains(&self.auth.bcrypt_cost) {
+            problems.push(format!(
+                "auth.bcrypt_cost must be between {} and 31",
+                bcrypt::DEFAULT_COST - 2
+            ));
+        }
+
+        if !self.mail.from.contains('@') {
+            problems.push(format!(
+                "mail.from must be email address but got {:?}",
+                self.mail.from
+            ));
+        }
+        if self.mail.transport == MailTransport::File && self.mail.dir.is_empty() {
+            problems.push("mail.dir must be set for file transport".into());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ApplicationError::InvalidConfig(problems.join("; ")))
+        }
+    }
+}
+
+fn set_var<T: FromStr>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    target: &mut T,
+) -> ApplicationResult<()>
+where
+    T::Err: std::fmt::Display,
+{
+    if let Some(value) = lookup(key) {
+        *target = value
+            .trim()
+            .parse()
+            .map_err(|err| ApplicationError::InvalidConfig(format!("{}: {}", key, err)))?;
+    }
+    Ok(())
+}
+
+// * Comma separated list
+fn set_list(lookup: &impl Fn(&str) -> Option<String>, key: &str, target: &mut Vec<String>) {
+    if let Some(value) = lookup(key) {
+        *target = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(String::from)
+            .collect();
+    }
+}
+
+fn merge_table(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge_table(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::adapters::database::TransactionOptions;
+use crate::domain::commands::Command;
+use crate::json_response;
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct RegisterAccount {
+    pub email: String,
+    pub password: String,
+    pub nickname: String,
+}
+
+/// Client the login is attempted from is recorded for audit.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct Login {
+    pub email: String,
+    pub password: String,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct RefreshToken {
+    pub refresh_token: String,
+}
+
+/// Token delivered by mail on registration.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct VerifyEmail {
+    pub token: String,
+}
+
+/// Verification token is issued again, replacing the one delivered before.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct RequestVerification {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct RequestPasswordReset {
+    pub email: String,
+}
+
+/// Token delivered by mail on `RequestPasswordReset`.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct ResetPassword {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct ChangePassword {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Lift lock on the account, restoring it from `Blocked` as well. Only moderator is allowed to.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct UnblockAccount {
+    pub account_id: String,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub moderator: bool,
+}
+
+/// Start TOTP enrollment, which is required on login only once confirmed with `ConfirmTotp`.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct EnableTotp {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+}
+
+/// Code generated by authenticator app with the secret given on `EnableTotp`.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct ConfirmTotp {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+    pub code: String,
+}
+
+/// Code is either TOTP code or one of recovery codes.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct DisableTotp {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+    pub code: String,
+}
+
+/// Second step of login for the account with second factor, where `mfa_token` is what `Login` handed out.
+/// Code is either TOTP code or one of recovery codes.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct VerifyMfa {
+    pub mfa_token: String,
+    pub code: String,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub ip: Option<String>,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub user_agent: Option<String>,
+}
+
+/// Sessions of the account, where the one the request is made with is marked as current.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct ListSessions {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub current_session_id: Option<Uuid>,
+}
+
+/// End the session, which logs out the device it is on.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct RevokeSession {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+    pub session_id: Uuid,
+}
+
+/// End every session of the account, which is to log out everywhere.
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
+pub struct RevokeAllSessions {
+    #[serde(default)]
+    #[schema(read_only)]
+    pub account_id: String,
+}
+
+/// Tokens issued on login and refresh.
+/// Refresh token is given out in plain only here, as it is kept hashed.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct IssuedToken {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+impl IssuedToken {
+    pub fn bearer(access_token: String, refresh_token: String, expires_in: i64) -> Self {
+        Self {
+            access_token,
+            refresh_token,
+            token_type: "Bearer".into(),
+            expires_in,
+        }
+    }
+}
+
+/// Handed out by `Login` in place of tokens when the account has second factor.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct MfaChallenge {
+    pub mfa_token: String,
+    pub expires_in: i64,
+}
+
+/// Handed out once on `EnableTotp`, as the secret is kept encrypted and recovery codes hashed.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct TotpEnrollment {
+    /// Base32, to be entered into authenticator app.
+    pub secret: String,
+    pub provisioning_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+/// Session listed on `ListSessions`, which leaves out refresh token hashes.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct SessionSummary {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[schema(value_type = String)]
+    pub create_dt: DateTime<Utc>,
+    #[schema(value_type = String)]
+    pub last_seen_dt: DateTime<Utc>,
+    #[schema(value_type = String)]
+    pub expiry_date: DateTime<Utc>,
+    pub current: bool,
+}
+
+json_response!(
+    IssuedToken,
+    MfaChallenge,
+    TotpEnrollment,
+    Vec<SessionSummary>
+);
+
+impl Command for RegisterAccount {
+    const NAME: &'static str = "RegisterAccount";
+    const SECRETS: &'static [&'static str] = &["password"];
+}
+impl Command for Login {
+    const NAME: &'static str = "Login";
+    const SECRETS: &'static [&'static str] = &["password"];
+}
+impl Command for RefreshToken {
+    const NAME: &'static str = "RefreshToken";
+    const SECRETS: &'static [&'static str] = &["refresh_token"];
+}
+impl Command for VerifyEmail {
+    const NAME: &'static str = "VerifyEmail";
+    const SECRETS: &'static [&'static str] = &["token"];
+}
+impl Command for RequestVerification {
+    const NAME: &'static str = "RequestVerification";
+}
+impl Command for RequestPasswordReset {
+    const NAME: &'static str = "RequestPasswordReset";
+}
+impl Command for ResetPassword {
+    const NAME: &'static str = "ResetPassword";
+    const SECRETS: &'static [&'static str] = &["token", "new_password"];
+}
+impl Command for ChangePassword {
+    const NAME: &'static str = "ChangePassword";
+    const SECRETS: &'static [&'static str] = &["current_password", "new_password"];
+}
+impl Command for UnblockAccount {
+    const NAME: &'static str = "UnblockAccount";
+}
+impl Command for EnableTotp {
+    const NAME: &'static str = "EnableTotp";
+}
+impl Command for ConfirmTotp {
+    const NAME: &'static str = "ConfirmTotp";
+    const SECRETS: &'static [&'static str] = &["code"];
+}
+impl Command for DisableTotp {
+    const NAME: &'static str = "DisableTotp";
+    const SECRETS: &'static [&'static str] = &["code"];
+}
+impl Command for VerifyMfa {
+    const NAME: &'static str = "VerifyMfa";
+    const SECRETS: &'static [&'static str] = &["mfa_token", "code"];
+}
+impl Command for ListSessions {
+    const NAME: &'static str = "ListSessions";
+
+    fn transaction_options(&self) -> TransactionOptions {
+        TransactionOptions::read_only()
+    }
+}
+impl Command for RevokeSession {
+    const NAME: &'static str = "RevokeSession";
+}
+impl Command for RevokeAllSessions {
+    const NAME: &'static str = "RevokeAllSessions";
+}
@@ -1,8 +1,19 @@
-use chrono::{DateTime, Utc};
+use std::mem;
+
+use chrono::{DateTime, Duration, Utc};
+use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::utils::{ApplicationError, ApplicationResult};
+
+/// How many refresh tokens rotated out are kept per family to catch reuse.
+/// Older ones are forgotten, and presenting one of them is rejected as invalid rather than as reuse.
+pub const MAX_ROTATED_REFRESH_TOKEN_HASHES: usize = 16;
 
 #[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash, sqlx::Type, Debug)]
-#[sqlx(type_name = "board_state")]
+#[sqlx(type_name = "account_state")]
 pub enum AccountState {
     VerificationRequired,
     Created,
@@ -16,51 +27,297 @@ impl Default for AccountState {
     }
 }
 
-#[derive(Default)]
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Copy, Hash, sqlx::Type, Debug, Default)]
+#[sqlx(type_name = "account_role")]
+pub enum AccountRole {
+    #[default]
+    Member,
+    Moderator,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct Account {
     //root
-    pub(crate) id: String,
-    pub(crate) email: String,
-    pub(crate) state: AccountState,
+    pub id: String,
+    pub email: String,
+    pub state: AccountState,
+    pub role: AccountRole,
 
-    pub(super) hashed_password: String,
-    pub(crate) nickname: String,
-    pub(crate) create_dt: DateTime<Utc>,
-    pub(crate) version: i32,
+    pub(crate) hashed_password: String,
+    pub nickname: String,
+    pub create_dt: DateTime<Utc>,
+    // * Kept after the lock expires, as failures before it no longer count toward the next lock.
+    pub locked_until: Option<DateTime<Utc>>,
+    pub version: i32,
 }
 
 impl Account {
-    pub fn new(id: String, email: String, password: String, nickname: String) -> Self {
+    /// Password is given already hashed, as hashing is left to `PasswordHasher` off the async workers.
+    pub fn new(id: String, email: String, hashed_password: String, nickname: String) -> Self {
         Self {
             id,
             email,
             state: Default::default(),
-            hashed_password: Self::hash_password(password),
+            role: Default::default(),
+            hashed_password,
             nickname,
-            create_dt: Default::default(),
+            create_dt: Utc::now(),
+            locked_until: None,
             version: 0,
         }
     }
-    pub fn hash_password(plain_password: String) -> String {
-        bcrypt::hash(plain_password, 4).unwrap()
+    pub fn hashed_password(&self) -> &str {
+        &self.hashed_password
     }
-    pub fn verify_password(&self, plain_password: impl AsRef<str>) -> bool {
-        bcrypt::verify(plain_password.as_ref(), &self.hashed_password).is_ok()
+
+    pub fn is_locked(&self) -> bool {
+        self.locked_until
+            .map_or(false, |locked_until| locked_until > Utc::now())
+    }
+
+    /// Start of the window within which failed logins count toward lock, which never goes back beyond the last lock.
+    pub fn failure_window_start(&self, window: Duration) -> DateTime<Utc> {
+        let start = Utc::now() - window;
+        match self.locked_until {
+            Some(locked_until) if locked_until > start => locked_until,
+            _ => start,
+        }
     }
 }
-#[derive(Default)]
+/// Session on a device, which is the refresh token family started on login.
+/// Refresh tokens are kept hashed, and each is swapped for new one on refresh until the family expires.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct TokenStat {
-    pub access_token: String,
-    pub refresh_token: String,
+    pub id: Uuid,
+    pub refresh_token_hash: String,
+    // * Latest refresh tokens rotated out, kept so that any of them presented again is caught as reuse.
+    pub rotated_refresh_token_hashes: Vec<String>,
     pub expiry_date: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub create_dt: DateTime<Utc>,
+    // * Updated on refresh, so it lags behind the device by no more than access token TTL.
+    pub last_seen_dt: DateTime<Utc>,
 }
 
 impl TokenStat {
-    pub fn create_token(&mut self) {
-        todo!()
+    /// Start new family for the device that expires after `ttl`, returned together with its refresh token in plain.
+    pub fn create_token(
+        ttl: Duration,
+        user_agent: Option<String>,
+        ip: Option<String>,
+    ) -> (Self, String) {
+        let refresh_token = generate_token();
+        let now = Utc::now();
+        let token_stat = Self {
+            id: Uuid::new_v4(),
+            refresh_token_hash: hash_token(&refresh_token),
+            rotated_refresh_token_hashes: vec![],
+            expiry_date: now + ttl,
+            user_agent,
+            ip,
+            create_dt: now,
+            last_seen_dt: now,
+        };
+        (token_stat, refresh_token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expiry_date <= Utc::now()
+    }
+
+    /// Whether the refresh token given belongs to this family, either as current one or one rotated out.
+    pub fn owns(&self, refresh_token: &str) -> bool {
+        let hash = hash_token(refresh_token);
+        self.refresh_token_hash == hash || self.rotated_refresh_token_hashes.contains(&hash)
+    }
+
+    /// Swap refresh token given for new one, which is returned in plain.
+    /// Refresh token that has already been rotated out is rejected as `TokenReused`.
+    pub fn rotate(&mut self, refresh_token: &str) -> ApplicationResult<String> {
+        let hash = hash_token(refresh_token);
+        if self.rotated_refresh_token_hashes.contains(&hash) {
+            return Err(ApplicationError::TokenReused);
+        }
+        if hash != self.refresh_token_hash || self.is_expired() {
+            return Err(ApplicationError::InvalidToken);
+        }
+
+        let new_refresh_token = generate_token();
+        let rotated = mem::replace(
+            &mut self.refresh_token_hash,
+            hash_token(&new_refresh_token),
+        );
+        self.rotated_refresh_token_hashes.push(rotated);
+        if let Some(excess) = self
+            .rotated_refresh_token_hashes
+            .len()
+            .checked_sub(MAX_ROTATED_REFRESH_TOKEN_HASHES)
+        {
+            self.rotated_refresh_token_hashes.drain(..excess);
+        }
+        self.last_seen_dt = Utc::now();
+        Ok(new_refresh_token)
+    }
+
+    pub fn hash_refresh_token(refresh_token: &str) -> String {
+        hash_token(refresh_token)
+    }
+}
+
+/// Token sent by mail to verify the email of the account, which is single-use and kept hashed.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct EmailVerification {
+    pub token_hash: String,
+    pub expiry_date: DateTime<Utc>,
+    pub requested_dt: DateTime<Utc>,
+}
+
+impl EmailVerification {
+    /// Issue token that expires after `ttl`, returned in plain together.
+    pub fn create_token(ttl: Duration) -> (Self, String) {
+        let token = generate_token();
+        let now = Utc::now();
+        let verification = Self {
+            token_hash: hash_token(&token),
+            expiry_date: now + ttl,
+            requested_dt: now,
+        };
+        (verification, token)
+    }
+
+    /// Whether token can be issued again, which is only after `interval` has passed since this one.
+    pub fn can_request_again(&self, interval: Duration) -> bool {
+        self.requested_dt + interval <= Utc::now()
+    }
+
+    /// Token given must match and not be expired.
+    pub fn verify(&self, token: &str) -> ApplicationResult<()> {
+        if hash_token(token) != self.token_hash || self.expiry_date <= Utc::now() {
+            return Err(ApplicationError::InvalidToken);
+        }
+        Ok(())
+    }
+
+    pub fn hash_token(token: &str) -> String {
+        hash_token(token)
+    }
+}
+
+/// Token sent by mail to reset the password of the account, which is single-use and kept hashed.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PasswordReset {
+    pub token_hash: String,
+    pub expiry_date: DateTime<Utc>,
+    pub requested_dt: DateTime<Utc>,
+}
+
+impl PasswordReset {
+    /// Issue token that expires after `ttl`, returned in plain together.
+    pub fn create_token(ttl: Duration) -> (Self, String) {
+        let token = generate_token();
+        let now = Utc::now();
+        let password_reset = Self {
+            token_hash: hash_token(&token),
+            expiry_date: now + ttl,
+            requested_dt: now,
+        };
+        (password_reset, token)
+    }
+
+    /// Whether next reset can be requested, which is only after `interval` has passed since this one.
+    pub fn can_request_again(&self, interval: Duration) -> bool {
+        self.requested_dt + interval <= Utc::now()
+    }
+
+    /// Token given must match and not be expired.
+    pub fn verify(&self, token: &str) -> ApplicationResult<()> {
+        if hash_token(token) != self.token_hash || self.expiry_date <= Utc::now() {
+            return Err(ApplicationError::InvalidToken);
+        }
+        Ok(())
+    }
+
+    pub fn hash_token(token: &str) -> String {
+        hash_token(token)
     }
 }
 
+/// TOTP second factor, which is required on login only once confirmed with a code.
+/// Secret is kept encrypted, while recovery codes are kept hashed and each can be used once in place of a code.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TotpCredential {
+    pub encrypted_secret: String,
+    pub confirmed: bool,
+    pub recovery_code_hashes: Vec<String>,
+    // * Time step of the last code accepted, as the same code must not be accepted twice.
+    pub last_used_step: i64,
+}
+
+impl TotpCredential {
+    const RECOVERY_CODE_COUNT: usize = 10;
+
+    /// Start unconfirmed, returned together with recovery codes in plain.
+    pub fn create(encrypted_secret: String) -> (Self, Vec<String>) {
+        let recovery_codes: Vec<String> = (0..Self::RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+        let totp = Self {
+            encrypted_secret,
+            confirmed: false,
+            recovery_code_hashes: recovery_codes.iter().map(|code| hash_token(code)).collect(),
+            last_used_step: 0,
+        };
+        (totp, recovery_codes)
+    }
+
+    /// Accept code that matched at `step`, which must be later than the last one accepted.
+    pub fn accept_step(&mut self, step: Option<i64>) -> ApplicationResult<()> {
+        match step {
+            Some(step) if step > self.last_used_step => {
+                self.last_used_step = step;
+                Ok(())
+            }
+            _ => Err(ApplicationError::InvalidCredentials),
+        }
+    }
+
+    /// Use up the recovery code given.
+    pub fn use_recovery_code(&mut self, code: &str) -> ApplicationResult<()> {
+        let hash = hash_token(code.trim());
+        let Some(position) = self
+            .recovery_code_hashes
+            .iter()
+            .position(|recovery_code_hash| *recovery_code_hash == hash)
+        else {
+            return Err(ApplicationError::InvalidCredentials);
+        };
+        self.recovery_code_hashes.remove(position);
+        Ok(())
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect()
+}
+
+// * Lowercase only, as it is typed in by hand.
+fn generate_recovery_code() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect()
+}
+
 #[test]
 fn test_create_hashed_password() {
     let hashed_password = bcrypt::hash("whatever", 4).unwrap();
@@ -73,3 +330,25 @@ fn test_verify_password() {
     assert_ne!(hashed_password, "whatever".to_string());
     assert!(bcrypt::verify("whatever", &hashed_password).unwrap());
 }
+
+#[test]
+fn test_rotate_refresh_token() {
+    let (mut token_stat, first) = TokenStat::create_token(Duration::days(1), None, None);
+    assert_ne!(token_stat.refresh_token_hash, first);
+
+    let second = token_stat.rotate(&first).unwrap();
+    assert_ne!(first, second);
+    assert!(token_stat.rotate("unknown").is_err());
+
+    // * First one has been rotated out.
+    let Err(ApplicationError::TokenReused) = token_stat.rotate(&first) else {
+        panic!("Reuse must be detected!")
+    };
+    assert!(token_stat.rotate(&second).is_ok());
+
+    // * Expired family is not refreshed.
+    let (mut token_stat, token) = TokenStat::create_token(Duration::seconds(-1), None, None);
+    let Err(ApplicationError::InvalidToken) = token_stat.rotate(&token) else {
+        panic!("Expired refresh token must be rejected!")
+    };
+}
@@ -1,4 +1,4 @@
-use uuid::Uuid;
+use chrono::{DateTime, Utc};
 
 use super::entity::AccountState;
 use crate::{
@@ -9,20 +9,71 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
 pub struct AccountCreated {
-    id: Uuid,
-    author: Uuid,
-    title: String,
-    content: String,
-    state: AccountState,
+    pub(crate) id: String,
+    pub(crate) email: String,
+    pub(crate) nickname: String,
+    pub(crate) state: AccountState,
 }
 
+/// Verification token is carried in plain, as it is to be delivered by mail once relayed through outbox.
 #[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
-pub struct AccountUpdated {
-    id: Uuid,
-    title: Option<String>,
-    content: Option<String>,
-    state: Option<AccountState>,
+pub struct VerificationRequested {
+    pub(crate) id: String,
+    pub(crate) email: String,
+    pub(crate) token: String,
+    pub(crate) expiry_date: DateTime<Utc>,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct EmailVerified {
+    pub(crate) id: String,
+    pub(crate) email: String,
+}
+
+/// Reset token is carried in plain, as it is to be delivered by mail once relayed through outbox.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct PasswordResetRequested {
+    pub(crate) id: String,
+    pub(crate) email: String,
+    pub(crate) token: String,
+    pub(crate) expiry_date: DateTime<Utc>,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct PasswordChanged {
+    pub(crate) id: String,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct AccountLocked {
+    pub(crate) id: String,
+    pub(crate) email: String,
+    pub(crate) failed_attempts: i64,
+    pub(crate) locked_until: DateTime<Utc>,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct AccountUnblocked {
+    pub(crate) id: String,
+    pub(crate) email: String,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct TotpEnabled {
+    pub(crate) id: String,
+}
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct TotpDisabled {
+    pub(crate) id: String,
 }
 
 message!(AccountCreated);
-message!(AccountUpdated);
+message!(VerificationRequested, externally_notifiable);
+message!(EmailVerified);
+message!(PasswordResetRequested, externally_notifiable);
+message!(PasswordChanged);
+message!(AccountLocked, externally_notifiable);
+message!(AccountUnblocked, externally_notifiable);
+message!(TotpEnabled);
+message!(TotpDisabled);
@@ -1,10 +1,23 @@
-mod entity;
+pub mod commands;
+pub mod entity;
 pub mod events;
 use std::{collections::VecDeque, mem};
 
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::adapters::password::PasswordVerification;
 use crate::aggregate;
+use crate::utils::{ApplicationError, ApplicationResult};
 
-use self::entity::{Account, TokenStat};
+use self::commands::RegisterAccount;
+use self::entity::{
+    Account, AccountState, EmailVerification, PasswordReset, TokenStat, TotpCredential,
+};
+use self::events::{
+    AccountCreated, AccountLocked, AccountUnblocked, EmailVerified, PasswordChanged,
+    PasswordResetRequested, TotpDisabled, TotpEnabled, VerificationRequested,
+};
 
 use super::{
     builder::{Buildable, Builder},
@@ -16,10 +29,312 @@ aggregate!(AuthAggregate);
 #[derive(Default)]
 pub struct AuthAggregate {
     pub account: Account,
-    pub token_stat: TokenStat,
+    // * One for each device logged in, oldest first.
+    pub sessions: Vec<TokenStat>,
+    // * Present while email verification is pending.
+    pub verification: Option<EmailVerification>,
+    // * Present while password reset is pending.
+    pub password_reset: Option<PasswordReset>,
+    // * Present once TOTP enrollment has started, though required on login only after confirmed.
+    pub totp: Option<TotpCredential>,
     pub events: VecDeque<Box<dyn Message>>, //Event
 }
 
+impl AuthAggregate {
+    pub fn register_account(&mut self, cmd: RegisterAccount, hashed_password: String) {
+        self.account = Account::new(
+            Uuid::new_v4().to_string(),
+            cmd.email,
+            hashed_password,
+            cmd.nickname,
+        );
+        self.raise_event(Box::new(AccountCreated {
+            id: self.account.id.clone(),
+            email: self.account.email.clone(),
+            nickname: self.account.nickname.clone(),
+            state: self.account.state.clone(),
+        }))
+    }
+
+    /// Issue verification token that expires after `ttl`, replacing the one issued before, if any.
+    /// The token is handed over in plain only through `VerificationRequested`.
+    /// It can't be issued again until `interval` passes, on which it is rejected as `TooManyRequests`.
+    pub fn request_verification(
+        &mut self,
+        ttl: Duration,
+        interval: Duration,
+    ) -> ApplicationResult<()> {
+        if self.account.state != AccountState::VerificationRequired {
+            return Err(ApplicationError::Forbidden);
+        }
+        if let Some(verification) = self.verification.as_ref() {
+            if !verification.can_request_again(interval) {
+                return Err(ApplicationError::TooManyRequests);
+            }
+        }
+        let (verification, token) = EmailVerification::create_token(ttl);
+        self.raise_event(Box::new(VerificationRequested {
+            id: self.account.id.clone(),
+            email: self.account.email.clone(),
+            token,
+            expiry_date: verification.expiry_date,
+        }));
+        self.verification = Some(verification);
+        Ok(())
+    }
+
+    /// Verify email with the token issued, after which the token can no longer be used.
+    pub fn verify_email(&mut self, token: &str) -> ApplicationResult<()> {
+        let Some(verification) = self.verification.as_ref() else {
+            return Err(ApplicationError::InvalidToken);
+        };
+        verification.verify(token)?;
+        if self.account.state != AccountState::VerificationRequired {
+            return Err(ApplicationError::InvalidToken);
+        }
+
+        self.verification = None;
+        self.account.state = AccountState::Created;
+        self.raise_event(Box::new(EmailVerified {
+            id: self.account.id.clone(),
+            email: self.account.email.clone(),
+        }));
+        Ok(())
+    }
+
+    /// Issue reset token that expires after `ttl`, replacing the one issued before, if any.
+    /// Reset can't be requested again until `interval` passes, on which it is rejected as `TooManyRequests`.
+    /// Account not allowed to log in is given no token, which is not told apart from success.
+    pub fn request_password_reset(
+        &mut self,
+        ttl: Duration,
+        interval: Duration,
+    ) -> ApplicationResult<()> {
+        if matches!(
+            self.account.state,
+            AccountState::Deleted | AccountState::Blocked
+        ) {
+            return Ok(());
+        }
+        if let Some(password_reset) = self.password_reset.as_ref() {
+            if !password_reset.can_request_again(interval) {
+                return Err(ApplicationError::TooManyRequests);
+            }
+        }
+
+        let (password_reset, token) = PasswordReset::create_token(ttl);
+        self.raise_event(Box::new(PasswordResetRequested {
+            id: self.account.id.clone(),
+            email: self.account.email.clone(),
+            token,
+            expiry_date: password_reset.expiry_date,
+        }));
+        self.password_reset = Some(password_reset);
+        Ok(())
+    }
+
+    /// Reset password with the token issued, after which the token can no longer be used.
+    pub fn reset_password(&mut self, token: &str, hashed_password: String) -> ApplicationResult<()> {
+        let Some(password_reset) = self.password_reset.as_ref() else {
+            return Err(ApplicationError::InvalidToken);
+        };
+        password_reset.verify(token)?;
+
+        self.password_reset = None;
+        self.set_password(hashed_password);
+        Ok(())
+    }
+
+    /// Change password to the one hashed, given the current password has been verified.
+    pub fn change_password(
+        &mut self,
+        verification: PasswordVerification,
+        hashed_password: String,
+    ) -> ApplicationResult<()> {
+        if verification == PasswordVerification::Invalid {
+            return Err(ApplicationError::InvalidCredentials);
+        }
+        self.set_password(hashed_password);
+        Ok(())
+    }
+
+    // * Every refresh token issued is revoked, so that sessions started with the old password end.
+    fn set_password(&mut self, hashed_password: String) {
+        self.account.hashed_password = hashed_password;
+        self.sessions.clear();
+        self.raise_event(Box::new(PasswordChanged {
+            id: self.account.id.clone(),
+        }));
+    }
+
+    /// Pass password as the first factor. Outdated hash that the password has been rehashed with on verification is replaced.
+    /// Whether the account exists but is not allowed to log in is not told apart from wrong password.
+    pub fn authenticate(&mut self, verification: PasswordVerification) -> ApplicationResult<()> {
+        if self.account.is_locked() {
+            return Err(ApplicationError::AccountLocked);
+        }
+        if matches!(
+            self.account.state,
+            AccountState::Deleted | AccountState::Blocked
+        ) {
+            return Err(ApplicationError::InvalidCredentials);
+        }
+        match verification {
+            PasswordVerification::Invalid => return Err(ApplicationError::InvalidCredentials),
+            PasswordVerification::Verified => (),
+            PasswordVerification::Rehashed(hashed_password) => {
+                self.account.hashed_password = hashed_password
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether second factor must be passed on login, on top of password.
+    pub fn mfa_required(&self) -> bool {
+        self.totp.as_ref().map_or(false, |totp| totp.confirmed)
+    }
+
+    /// Start new session for the device once every factor required has been passed, returning its id and refresh token in plain.
+    /// Sessions expired are dropped on the way, as nothing can be done with them.
+    pub fn login(
+        &mut self,
+        refresh_token_ttl: Duration,
+        user_agent: Option<String>,
+        ip: Option<String>,
+    ) -> (Uuid, String) {
+        self.sessions.retain(|session| !session.is_expired());
+        let (session, refresh_token) = TokenStat::create_token(refresh_token_ttl, user_agent, ip);
+        let session_id = session.id;
+        self.sessions.push(session);
+        (session_id, refresh_token)
+    }
+
+    /// End the session given, after which neither its refresh token nor access tokens issued for it are accepted.
+    pub fn revoke_session(&mut self, session_id: Uuid) -> ApplicationResult<()> {
+        let Some(position) = self
+            .sessions
+            .iter()
+            .position(|session| session.id == session_id)
+        else {
+            return Err(ApplicationError::EntityNotFound);
+        };
+        self.sessions.remove(position);
+        Ok(())
+    }
+
+    /// End every session, which is to log out everywhere.
+    pub fn revoke_all_sessions(&mut self) {
+        self.sessions.clear();
+    }
+
+    /// Start TOTP enrollment with the secret encrypted, replacing the one pending confirmation, if any.
+    /// Recovery codes are returned in plain, which is the only time they are given out.
+    pub fn enable_totp(&mut self, encrypted_secret: String) -> ApplicationResult<Vec<String>> {
+        if self.mfa_required() {
+            return Err(ApplicationError::EntityAlreadyExists("totp".into()));
+        }
+        let (totp, recovery_codes) = TotpCredential::create(encrypted_secret);
+        self.totp = Some(totp);
+        Ok(recovery_codes)
+    }
+
+    /// Confirm TOTP pending with code that matched at `step`, after which it is required on login.
+    pub fn confirm_totp(&mut self, step: Option<i64>) -> ApplicationResult<()> {
+        let Some(totp) = self.totp.as_mut() else {
+            return Err(ApplicationError::EntityNotFound);
+        };
+        if totp.confirmed {
+            return Err(ApplicationError::EntityAlreadyExists("totp".into()));
+        }
+        totp.accept_step(step)?;
+        totp.confirmed = true;
+        self.raise_event(Box::new(TotpEnabled {
+            id: self.account.id.clone(),
+        }));
+        Ok(())
+    }
+
+    /// Remove TOTP, which takes second factor passed once it has been confirmed.
+    pub fn disable_totp(&mut self, code: &str, step: Option<i64>) -> ApplicationResult<()> {
+        if self.totp.is_none() {
+            return Err(ApplicationError::EntityNotFound);
+        }
+        if self.mfa_required() {
+            self.verify_second_factor(code, step)?;
+            self.raise_event(Box::new(TotpDisabled {
+                id: self.account.id.clone(),
+            }));
+        }
+        self.totp = None;
+        Ok(())
+    }
+
+    /// Pass second factor with either TOTP code that matched at `step` or one of recovery codes, which is then used up.
+    pub fn verify_second_factor(&mut self, code: &str, step: Option<i64>) -> ApplicationResult<()> {
+        if self.account.is_locked() {
+            return Err(ApplicationError::AccountLocked);
+        }
+        let Some(totp) = self.totp.as_mut().filter(|totp| totp.confirmed) else {
+            return Err(ApplicationError::InvalidCredentials);
+        };
+        totp.accept_step(step)
+            .or_else(|_| totp.use_recovery_code(code))
+    }
+
+    /// Lock the account for `duration` once failed logins within the window reach `max_failures`.
+    pub fn lock_on_failures(&mut self, failures: i64, max_failures: i64, duration: Duration) {
+        if failures < max_failures || self.account.is_locked() {
+            return;
+        }
+        let locked_until = Utc::now() + duration;
+        self.account.locked_until = Some(locked_until);
+        self.raise_event(Box::new(AccountLocked {
+            id: self.account.id.clone(),
+            email: self.account.email.clone(),
+            failed_attempts: failures,
+            locked_until,
+        }));
+    }
+
+    /// Lift the lock, if any, and restore the account from `Blocked`.
+    /// Failed logins before this no longer count toward the next lock.
+    pub fn unblock(&mut self, moderator: bool) -> ApplicationResult<()> {
+        if !moderator {
+            return Err(ApplicationError::Forbidden);
+        }
+        self.account.locked_until = Some(Utc::now());
+        if self.account.state == AccountState::Blocked {
+            self.account.state = AccountState::Created;
+        }
+        self.raise_event(Box::new(AccountUnblocked {
+            id: self.account.id.clone(),
+            email: self.account.email.clone(),
+        }));
+        Ok(())
+    }
+
+    /// Rotate refresh token, returning the id of its session and new one in plain.
+    /// On reuse of refresh token already rotated out, which may have been stolen, the session it belongs to is revoked.
+    pub fn refresh(&mut self, refresh_token: &str) -> ApplicationResult<(Uuid, String)> {
+        let Some(position) = self
+            .sessions
+            .iter()
+            .position(|session| session.owns(refresh_token))
+        else {
+            return Err(ApplicationError::InvalidToken);
+        };
+        let session = &mut self.sessions[position];
+        match session.rotate(refresh_token) {
+            Ok(new_refresh_token) => Ok((session.id, new_refresh_token)),
+            Err(ApplicationError::TokenReused) => {
+                self.sessions.remove(position);
+                Err(ApplicationError::TokenReused)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
 pub struct AuthAggregateBuilder(AuthAggregate);
 
 impl AuthAggregateBuilder {
@@ -27,8 +342,20 @@ impl AuthAggregateBuilder {
         self.0.account = account;
         self
     }
-    pub fn take_token_stat(mut self, token_stat: TokenStat) -> Self {
-        self.0.token_stat = token_stat;
+    pub fn take_session(mut self, session: TokenStat) -> Self {
+        self.0.sessions.push(session);
+        self
+    }
+    pub fn take_verification(mut self, verification: EmailVerification) -> Self {
+        self.0.verification = Some(verification);
+        self
+    }
+    pub fn take_password_reset(mut self, password_reset: PasswordReset) -> Self {
+        self.0.password_reset = Some(password_reset);
+        self
+    }
+    pub fn take_totp(mut self, totp: TotpCredential) -> Self {
+        self.0.totp = Some(totp);
         self
     }
 }
@@ -55,7 +382,7 @@ fn test_create_account() {
     let account = Account::new(
         "Migo".into(),
         "migo@mail.com".into(),
-        "testpass".into(),
+        bcrypt::hash("testpass", 4).unwrap(),
         "Mago".into(),
     );
     let auth_aggregate = auth_builder.take_account(account).build();
@@ -65,5 +392,4 @@ fn test_create_account() {
         "testpass".to_string()
     );
     assert!(bcrypt::verify("testpass", &auth_aggregate.account.hashed_password).unwrap());
-    assert!(auth_aggregate.account.verify_password("testpass"))
 }
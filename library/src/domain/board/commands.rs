@@ -1,42 +1,111 @@
-use serde::Deserialize;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
 
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::entity::BoardState;
-use crate::domain::commands::Command;
+use crate::domain::commands::{Command, RetryPolicy};
+
+// * `author`, `editor` and `moderator` are filled in from the account authenticated, not taken from request.
 
-#[derive(Debug, Deserialize, Clone, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct CreateBoard {
+    #[serde(default)]
+    #[schema(read_only)]
     pub author: Uuid,
     pub title: String,
     pub content: String,
     pub state: BoardState,
 }
 
-#[derive(Debug, Deserialize, Clone, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct EditBoard {
     pub id: Uuid,
     pub title: Option<String>,
     pub content: Option<String>,
     pub state: Option<BoardState>,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub editor: Uuid,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub moderator: bool,
 }
 
-#[derive(Debug, Deserialize, Clone, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct AddComment {
     pub board_id: Uuid,
+    #[serde(default)]
+    #[schema(read_only)]
     pub author: Uuid,
     pub content: String,
 }
 
-#[derive(Debug, Deserialize, Clone, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, Clone, ToSchema)]
 pub struct EditComment {
     pub board_id: Uuid,
     pub id: Uuid,
     pub content: String,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub editor: Uuid,
+    #[serde(default)]
+    #[schema(read_only)]
+    pub moderator: bool,
+}
+
+/// Moderation check step of `BoardModeration`, which is issued by the process rather than taken from request.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CheckBoardModeration {
+    pub board_id: Uuid,
 }
 
-impl Command for CreateBoard {}
-impl Command for EditBoard {}
-impl Command for AddComment {}
-impl Command for EditComment {}
+/// Take board down on behalf of the system, as when it fails moderation.
+/// It is issued by the process rather than taken from request, so no editor is checked.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TakeDownBoard {
+    pub id: Uuid,
+}
+
+// * Commands that update board may conflict with one another, so they are retried.
+const RETRY_ON_CONFLICT: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    backoff: Duration::from_millis(10),
+};
+
+impl Command for CreateBoard {
+    const NAME: &'static str = "CreateBoard";
+}
+impl Command for EditBoard {
+    const NAME: &'static str = "EditBoard";
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RETRY_ON_CONFLICT
+    }
+}
+impl Command for AddComment {
+    const NAME: &'static str = "AddComment";
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RETRY_ON_CONFLICT
+    }
+}
+impl Command for EditComment {
+    const NAME: &'static str = "EditComment";
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RETRY_ON_CONFLICT
+    }
+}
+impl Command for CheckBoardModeration {
+    const NAME: &'static str = "CheckBoardModeration";
+}
+impl Command for TakeDownBoard {
+    const NAME: &'static str = "TakeDownBoard";
+
+    fn retry_policy(&self) -> RetryPolicy {
+        RETRY_ON_CONFLICT
+    }
+}
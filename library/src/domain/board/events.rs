@@ -32,6 +32,14 @@ pub struct BoardCommentAdded {
     pub(crate) state: CommentState,
 }
 
+/// Result of the moderation check, which `BoardModeration` moves on with.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Hash)]
+pub struct BoardModerated {
+    pub(crate) id: Uuid,
+    pub(crate) approved: bool,
+}
+
 message!(BoardCreated, externally_notifiable, internally_notifiable);
+message!(BoardModerated, internally_notifiable);
 message!(BoardUpdated);
 message!(BoardCommentAdded);
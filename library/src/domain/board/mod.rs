@@ -1,14 +1,15 @@
 pub mod commands;
 pub mod entity;
 pub mod events;
+pub mod process;
 use std::{collections::VecDeque, mem};
 
 use crate::aggregate;
 use crate::utils::{ApplicationError, ApplicationResult};
 
 use self::commands::{AddComment, CreateBoard, EditBoard, EditComment};
-use self::entity::{Board, BoardState, Comment};
-use self::events::{BoardCommentAdded, BoardCreated, BoardUpdated};
+use self::entity::{Board, BoardState, Comment, CommentState};
+use self::events::{BoardCommentAdded, BoardCreated, BoardModerated, BoardUpdated};
 
 use super::builder::{Buildable, Builder};
 
@@ -32,7 +33,11 @@ impl BoardAggregate {
             state: self.board.state.clone(),
         }))
     }
-    pub fn update_board(&mut self, cmd: EditBoard) {
+    /// Board is edited only by its author or moderator.
+    pub fn update_board(&mut self, cmd: EditBoard) -> ApplicationResult<()> {
+        if !cmd.moderator && cmd.editor != self.board.author {
+            return Err(ApplicationError::Forbidden);
+        }
         if let Some(ref title) = cmd.title {
             self.board.title = title.clone();
         }
@@ -47,7 +52,8 @@ impl BoardAggregate {
             title: cmd.title,
             content: cmd.content,
             state: cmd.state,
-        }))
+        }));
+        Ok(())
     }
     pub fn add_comment(&mut self, cmd: AddComment) {
         let new_comment = Comment::new(self.board.id, cmd.author, &cmd.content);
@@ -64,13 +70,26 @@ impl BoardAggregate {
         self.board.state = BoardState::Deleted
     }
 
+    /// Record the result of moderation check, which changes nothing on the board itself.
+    pub fn moderate(&mut self, approved: bool) {
+        self.raise_event(Box::new(BoardModerated {
+            id: self.board.id,
+            approved,
+        }))
+    }
+
+    /// Comment is edited only by its author or moderator.
     pub fn edit_comment(&mut self, cmd: EditComment) -> ApplicationResult<()> {
         let comment = self
             .comments
             .iter_mut()
             .find(|c| c.id == cmd.id)
             .ok_or(ApplicationError::EntityNotFound)?;
+        if !cmd.moderator && cmd.editor != comment.author {
+            return Err(ApplicationError::Forbidden);
+        }
         comment.content = cmd.content;
+        comment.state = CommentState::UpdatePending;
         Ok(())
     }
 }
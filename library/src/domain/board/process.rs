@@ -0,0 +1,43 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::commands::TakeDownBoard;
+use crate::adapters::process::ProcessManager;
+use crate::adapters::scheduled_command::ScheduledCommand;
+use crate::utils::ApplicationResult;
+
+#[derive(Eq, PartialEq, Serialize, Deserialize, Clone, Debug)]
+pub enum ModerationStep {
+    Requested,
+    Approved,
+    Rejected,
+}
+
+/// Moderation check run on every board created, issued as `CheckBoardModeration` and moved on with `BoardModerated`.
+/// Board that fails the check, or is not moderated in time, is taken down.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BoardModeration {
+    pub board_id: Uuid,
+    pub step: ModerationStep,
+}
+
+impl BoardModeration {
+    pub fn new(board_id: Uuid) -> Self {
+        Self {
+            board_id,
+            step: ModerationStep::Requested,
+        }
+    }
+}
+
+impl ProcessManager for BoardModeration {
+    const PROCESS_TYPE: &'static str = "BoardModeration";
+
+    fn compensations(&self) -> ApplicationResult<Vec<ScheduledCommand>> {
+        Ok(vec![ScheduledCommand::new(
+            &TakeDownBoard { id: self.board_id },
+            Utc::now(),
+        )?])
+    }
+}
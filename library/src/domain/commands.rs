@@ -1,16 +1,61 @@
-use crate::utils::ApplicationError;
+use std::time::Duration;
 
-use serde::{self, Serialize};
+use crate::{
+    adapters::database::{ReadPreference, TransactionOptions},
+    utils::ApplicationError,
+};
+
+use serde::{self, Deserialize, Serialize};
 
 use uuid::Uuid;
 
-pub trait Command: 'static + Send {}
+pub trait Command: 'static + Send {
+    /// Name the command is persisted under, as in scheduled command and idempotency fingerprint.
+    /// It must stay the same across refactors, unlike `std::any::type_name`.
+    const NAME: &'static str;
+
+    /// Fields such as passwords and tokens, which are left out of the idempotency fingerprint.
+    const SECRETS: &'static [&'static str] = &[];
+
+    /// How the command is handled again when it fails on transient error such as concurrency conflict.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Options for the transactions the command is handled in, such as isolation level.
+    fn transaction_options(&self) -> TransactionOptions {
+        TransactionOptions::default()
+    }
+
+    /// Where the reads outside transaction go while the command is handled.
+    /// Set to `ReadPreference::Primary` for the command that has to read what has just been written.
+    fn read_preference(&self) -> ReadPreference {
+        ReadPreference::default()
+    }
+}
+
+/// Handling is attempted up to `max_attempts` times, waiting `backoff` multiplied by the number of attempts made in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::ZERO,
+        }
+    }
+}
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ServiceResponse {
     String(String),
     Bool(bool),
     Empty(()),
+    Json(serde_json::Value),
 }
 
 impl From<String> for ServiceResponse {
@@ -28,6 +73,11 @@ impl From<bool> for ServiceResponse {
         ServiceResponse::Bool(value)
     }
 }
+impl From<serde_json::Value> for ServiceResponse {
+    fn from(value: serde_json::Value) -> Self {
+        ServiceResponse::Json(value)
+    }
+}
 impl From<()> for ServiceResponse {
     fn from(_value: ()) -> Self {
         ServiceResponse::Empty(())
@@ -65,3 +115,33 @@ impl TryFrom<ServiceResponse> for bool {
         }
     }
 }
+
+/// Convert serializable type to and from `ServiceResponse::Json`, both of which may fail.
+#[macro_export]
+macro_rules! json_response {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl TryFrom<$ty> for $crate::domain::commands::ServiceResponse {
+                type Error = $crate::utils::ApplicationError;
+
+                fn try_from(value: $ty) -> Result<Self, Self::Error> {
+                    serde_json::to_value(value)
+                        .map(Self::Json)
+                        .map_err(|err| $crate::utils::ApplicationError::SerializationError(Box::new(err)))
+                }
+            }
+
+            impl TryFrom<$crate::domain::commands::ServiceResponse> for $ty {
+                type Error = $crate::utils::ApplicationError;
+
+                fn try_from(value: $crate::domain::commands::ServiceResponse) -> Result<Self, Self::Error> {
+                    match value {
+                        $crate::domain::commands::ServiceResponse::Json(value) => serde_json::from_value(value)
+                            .map_err(|_| $crate::utils::ApplicationError::ParsingError),
+                        _ => Err($crate::utils::ApplicationError::ParsingError),
+                    }
+                }
+            }
+        )+
+    };
+}
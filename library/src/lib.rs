@@ -1,5 +1,6 @@
 pub mod adapters;
 pub mod bootstrap;
+pub mod config;
 pub mod domain;
 pub mod services;
 pub mod utils;
@@ -1,17 +1,41 @@
 use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::Utc;
 
 use crate::adapters::database::AtomicContextManager;
+use crate::adapters::jwt::{AccessToken, MfaToken};
+use crate::adapters::login_attempt::LoginAttempt;
+use crate::adapters::mailer::{Mail, Mailer};
 use crate::adapters::outbox::Outbox;
-use crate::adapters::repositories::{Repository};
+use crate::adapters::password::{self, PasswordHasher, PasswordVerification};
+use crate::adapters::process::{Process, ProcessStatus};
+use crate::adapters::repositories::Repository;
+use crate::adapters::totp::{SecretCipher, Totp};
 
-use crate::domain::board::commands::{AddComment, CreateBoard, EditBoard, EditComment};
+use crate::bootstrap::config;
+use crate::domain::auth::commands::{
+    ChangePassword, ConfirmTotp, DisableTotp, EnableTotp, IssuedToken, ListSessions, Login,
+    MfaChallenge, RefreshToken, RegisterAccount, RequestPasswordReset, RequestVerification,
+    ResetPassword, RevokeAllSessions, RevokeSession, SessionSummary, TotpEnrollment,
+    UnblockAccount, VerifyEmail, VerifyMfa,
+};
+use crate::domain::auth::entity::{EmailVerification, PasswordReset, TokenStat};
+use crate::domain::auth::events::{
+    AccountLocked, AccountUnblocked, PasswordResetRequested, VerificationRequested,
+};
+use crate::domain::auth::AuthAggregate;
+use crate::domain::board::commands::{
+    AddComment, CheckBoardModeration, CreateBoard, EditBoard, EditComment, TakeDownBoard,
+};
 
 use crate::domain::board::BoardAggregate;
 
-use crate::domain::board::events::BoardCreated;
+use crate::domain::board::events::{BoardCreated, BoardModerated};
+use crate::domain::board::process::{BoardModeration, ModerationStep};
 use crate::domain::builder::{Buildable, Builder};
 use crate::domain::commands::ServiceResponse;
-use crate::utils::ApplicationResult;
+use crate::utils::{ApplicationError, ApplicationResult};
 
 use super::unit_of_work::UnitOfWork;
 pub type Future<T> = Pin<Box<dyn futures::Future<Output = ApplicationResult<T>> + Send>>;
@@ -23,10 +47,7 @@ impl ServiceHandler {
         context: AtomicContextManager,
     ) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let mut uow =
-                UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
-                    .await?;
-            
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
             let builder = BoardAggregate::builder();
             let mut board_aggregate: BoardAggregate = builder.build();
             board_aggregate.create_board(cmd);
@@ -39,12 +60,9 @@ impl ServiceHandler {
 
     pub fn edit_board(cmd: EditBoard, context: AtomicContextManager) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let mut uow =
-                UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
-                    .await?;
-            
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
             let mut board_aggregate = uow.repository().get(&cmd.id.to_string()).await?;
-            board_aggregate.update_board(cmd);
+            board_aggregate.update_board(cmd)?;
             uow.repository().update(&mut board_aggregate).await?;
             uow.commit().await?;
 
@@ -54,10 +72,7 @@ impl ServiceHandler {
 
     pub fn add_comment(cmd: AddComment, context: AtomicContextManager) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let mut uow =
-                UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
-                    .await?;
-            
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
             let mut board_aggregate = uow.repository().get(&cmd.board_id.to_string()).await?;
             board_aggregate.add_comment(cmd);
             uow.repository().update(&mut board_aggregate).await?;
@@ -71,10 +86,7 @@ impl ServiceHandler {
         context: AtomicContextManager,
     ) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let mut uow =
-                UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
-                    .await?;
-            
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
             let mut board_aggregate = uow.repository().get(&cmd.board_id.to_string()).await?;
             board_aggregate.edit_comment(cmd)?;
             uow.repository().update(&mut board_aggregate).await?;
@@ -83,17 +95,587 @@ impl ServiceHandler {
         })
     }
 
-    pub fn handle_outbox(outbox: Outbox, context: AtomicContextManager) -> Future<ServiceResponse> {
+    /// Moderation check step of `BoardModeration`, whose result is raised as `BoardModerated`.
+    pub fn check_board_moderation(
+        cmd: CheckBoardModeration,
+        context: AtomicContextManager,
+        moderation_check: fn(&str, &str) -> bool,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
+
+            let mut board_aggregate = uow.repository().get(&cmd.board_id.to_string()).await?;
+            let approved =
+                moderation_check(&board_aggregate.board.title, &board_aggregate.board.content);
+            board_aggregate.moderate(approved);
+            uow.repository().update(&mut board_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn take_down_board(
+        cmd: TakeDownBoard,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
+
+            let mut board_aggregate = uow.repository().get(&cmd.id.to_string()).await?;
+            board_aggregate.delete();
+            uow.repository().update(&mut board_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn register_account(
+        cmd: RegisterAccount,
+        context: AtomicContextManager,
+        password_hasher: Arc<dyn PasswordHasher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let hashed_password =
+                password::hash_password(password_hasher, cmd.password.clone()).await?;
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = AuthAggregate::builder().build();
+            auth_aggregate.register_account(cmd, hashed_password);
+            auth_aggregate.request_verification(
+                config().auth.verification_token_ttl(),
+                config().auth.verification_request_interval(),
+            )?;
+
+            let res = uow.repository().add(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(res.into())
+        })
+    }
+
+    /// Every attempt is recorded for audit, and the account is locked once failures within the window reach the limit.
+    /// Records and lock are committed even though the login fails.
+    /// Account with second factor is handed `MfaChallenge` in place of tokens, which are issued on `VerifyMfa`.
+    pub fn login(
+        cmd: Login,
+        context: AtomicContextManager,
+        password_hasher: Arc<dyn PasswordHasher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+            let auth_config = &config().auth;
+            let attempt = |account_id: Option<&str>, succeeded: bool| {
+                LoginAttempt::new(
+                    account_id.map(String::from),
+                    cmd.email.as_str(),
+                    cmd.ip.clone(),
+                    cmd.user_agent.clone(),
+                    succeeded,
+                )
+            };
+
+            let mut auth_aggregate = match uow.repository().get_by_email(&cmd.email).await {
+                Ok(auth_aggregate) => auth_aggregate,
+                Err(ApplicationError::EntityNotFound) => {
+                    // * Unknown email takes as long as wrong password, so that it doesn't tell which accounts exist.
+                    password::verify_dummy_password(password_hasher, cmd.password.clone()).await?;
+                    attempt(None, false).add(uow.executor()).await?;
+                    uow.commit().await?;
+                    return Err(ApplicationError::InvalidCredentials);
+                }
+                Err(err) => Err(err)?,
+            };
+            let account_id = auth_aggregate.account.id.clone();
+
+            // * Password is not verified while locked, though it takes as long as if it were.
+            if auth_aggregate.account.is_locked() {
+                password::verify_dummy_password(password_hasher, cmd.password.clone()).await?;
+                attempt(Some(&account_id), false)
+                    .add(uow.executor())
+                    .await?;
+                uow.commit().await?;
+                return Err(ApplicationError::AccountLocked);
+            }
+
+            let verification = password::verify_password(
+                password_hasher,
+                cmd.password.clone(),
+                auth_aggregate.account.hashed_password().to_string(),
+            )
+            .await?;
+            match auth_aggregate.authenticate(verification) {
+                Ok(()) => (),
+                Err(ApplicationError::InvalidCredentials) => {
+                    let attempt = attempt(Some(&account_id), false);
+                    return Self::fail_login(uow, auth_aggregate, attempt).await;
+                }
+                Err(err) => Err(err)?,
+            };
+
+            // * Attempt is recorded on second factor, as password alone doesn't complete login.
+            if auth_aggregate.mfa_required() {
+                uow.repository().update(&mut auth_aggregate).await?;
+                uow.commit().await?;
+                return Ok(MfaChallenge {
+                    mfa_token: MfaToken::issue(&auth_aggregate.account)?,
+                    expires_in: auth_config.mfa_token_ttl().num_seconds(),
+                }
+                .try_into()?);
+            }
+
+            let (session_id, refresh_token) = auth_aggregate.login(
+                auth_config.refresh_token_ttl(),
+                cmd.user_agent.clone(),
+                cmd.ip.clone(),
+            );
+            attempt(Some(&account_id), true)
+                .add(uow.executor())
+                .await?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+
+            Ok(IssuedToken::bearer(
+                AccessToken::issue(&auth_aggregate.account, session_id)?,
+                refresh_token,
+                auth_config.access_token_ttl().num_seconds(),
+            )
+            .try_into()?)
+        })
+    }
+
+    /// Second step of login, where failure counts toward lock just as wrong password does.
+    pub fn verify_mfa(
+        cmd: VerifyMfa,
+        context: AtomicContextManager,
+        secret_cipher: Arc<SecretCipher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let claims = MfaToken::verify(&cmd.mfa_token)?;
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+            let auth_config = &config().auth;
+
+            let mut auth_aggregate = uow.repository().get(&claims.sub).await?;
+            let email = auth_aggregate.account.email.clone();
+            let attempt = |succeeded: bool| {
+                LoginAttempt::new(
+                    Some(claims.sub.clone()),
+                    email.as_str(),
+                    cmd.ip.clone(),
+                    cmd.user_agent.clone(),
+                    succeeded,
+                )
+            };
+
+            let step = Self::totp_step(&auth_aggregate, &secret_cipher, &cmd.code)?;
+            match auth_aggregate.verify_second_factor(&cmd.code, step) {
+                Ok(()) => (),
+                Err(ApplicationError::InvalidCredentials) => {
+                    let attempt = attempt(false);
+                    return Self::fail_login(uow, auth_aggregate, attempt).await;
+                }
+                Err(ApplicationError::AccountLocked) => {
+                    attempt(false).add(uow.executor()).await?;
+                    uow.commit().await?;
+                    return Err(ApplicationError::AccountLocked);
+                }
+                Err(err) => Err(err)?,
+            };
+
+            let (session_id, refresh_token) = auth_aggregate.login(
+                auth_config.refresh_token_ttl(),
+                cmd.user_agent.clone(),
+                cmd.ip.clone(),
+            );
+            attempt(true).add(uow.executor()).await?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+
+            Ok(IssuedToken::bearer(
+                AccessToken::issue(&auth_aggregate.account, session_id)?,
+                refresh_token,
+                auth_config.access_token_ttl().num_seconds(),
+            )
+            .try_into()?)
+        })
+    }
+
+    // * Failure is recorded and counted toward lock, all of which is committed even though the login fails.
+    async fn fail_login(
+        mut uow: UnitOfWork<Repository<AuthAggregate>>,
+        mut auth_aggregate: AuthAggregate,
+        attempt: LoginAttempt,
+    ) -> ApplicationResult<ServiceResponse> {
+        let auth_config = &config().auth;
+        attempt.add(uow.executor()).await?;
+        let failures = LoginAttempt::count_failures(
+            uow.executor(),
+            &auth_aggregate.account.id,
+            auth_aggregate
+                .account
+                .failure_window_start(auth_config.lockout_window()),
+        )
+        .await?;
+        auth_aggregate.lock_on_failures(
+            failures,
+            auth_config.lockout_max_failures,
+            auth_config.lockout_duration(),
+        );
+        uow.repository().update(&mut auth_aggregate).await?;
+        uow.commit().await?;
+        Err(ApplicationError::InvalidCredentials)
+    }
+
+    // * Time step the code matches with the secret of the account, if any.
+    fn totp_step(
+        auth_aggregate: &AuthAggregate,
+        secret_cipher: &SecretCipher,
+        code: &str,
+    ) -> ApplicationResult<Option<i64>> {
+        let Some(totp) = auth_aggregate.totp.as_ref() else {
+            return Ok(None);
+        };
+        let secret = secret_cipher.decrypt(&totp.encrypted_secret)?;
+        Ok(Totp::new(secret).verify(code.trim(), Utc::now()))
+    }
+
+    pub fn refresh_token(cmd: RefreshToken, context: AtomicContextManager) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let refresh_token_hash = TokenStat::hash_refresh_token(&cmd.refresh_token);
+            let mut auth_aggregate = match uow
+                .repository()
+                .get_by_refresh_token_hash(&refresh_token_hash)
+                .await
+            {
+                Ok(auth_aggregate) => auth_aggregate,
+                Err(ApplicationError::EntityNotFound) => Err(ApplicationError::InvalidToken)?,
+                Err(err) => Err(err)?,
+            };
+
+            let (session_id, refresh_token) = match auth_aggregate.refresh(&cmd.refresh_token) {
+                Ok(refreshed) => refreshed,
+                // * Revocation must be committed even though the request fails.
+                Err(ApplicationError::TokenReused) => {
+                    tracing::warn!(
+                        account_id = auth_aggregate.account.id,
+                        "refresh token reused, revoking session"
+                    );
+                    uow.repository().update(&mut auth_aggregate).await?;
+                    uow.commit().await?;
+                    return Err(ApplicationError::TokenReused);
+                }
+                Err(err) => Err(err)?,
+            };
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+
+            Ok(IssuedToken::bearer(
+                AccessToken::issue(&auth_aggregate.account, session_id)?,
+                refresh_token,
+                config().auth.access_token_ttl().num_seconds(),
+            )
+            .try_into()?)
+        })
+    }
+
+    pub fn verify_email(cmd: VerifyEmail, context: AtomicContextManager) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let token_hash = EmailVerification::hash_token(&cmd.token);
+            let mut auth_aggregate = match uow
+                .repository()
+                .get_by_verification_token_hash(&token_hash)
+                .await
+            {
+                Ok(auth_aggregate) => auth_aggregate,
+                Err(ApplicationError::EntityNotFound) => Err(ApplicationError::InvalidToken)?,
+                Err(err) => Err(err)?,
+            };
+            auth_aggregate.verify_email(&cmd.token)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    /// Unknown email, account already verified, and request made too soon are not told apart from success,
+    /// so that whether an account exists is not revealed.
+    pub fn request_verification(
+        cmd: RequestVerification,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+            let auth_config = &config().auth;
+
+            let mut auth_aggregate = match uow.repository().get_by_email(&cmd.email).await {
+                Ok(auth_aggregate) => auth_aggregate,
+                Err(ApplicationError::EntityNotFound) => {
+                    uow.rollback().await?;
+                    return Ok(().into());
+                }
+                Err(err) => Err(err)?,
+            };
+            match auth_aggregate.request_verification(
+                auth_config.verification_token_ttl(),
+                auth_config.verification_request_interval(),
+            ) {
+                Ok(()) => (),
+                Err(ApplicationError::Forbidden | ApplicationError::TooManyRequests) => {
+                    uow.rollback().await?;
+                    return Ok(().into());
+                }
+                Err(err) => Err(err)?,
+            };
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    /// Unknown email and request made too soon are not told apart from success,
+    /// so that whether an account exists is not revealed.
+    pub fn request_password_reset(
+        cmd: RequestPasswordReset,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+            let auth_config = &config().auth;
+
+            let mut auth_aggregate = match uow.repository().get_by_email(&cmd.email).await {
+                Ok(auth_aggregate) => auth_aggregate,
+                Err(ApplicationError::EntityNotFound) => {
+                    uow.rollback().await?;
+                    return Ok(().into());
+                }
+                Err(err) => Err(err)?,
+            };
+            match auth_aggregate.request_password_reset(
+                auth_config.password_reset_token_ttl(),
+                auth_config.password_reset_interval(),
+            ) {
+                Ok(()) => (),
+                Err(ApplicationError::TooManyRequests) => {
+                    uow.rollback().await?;
+                    return Ok(().into());
+                }
+                Err(err) => Err(err)?,
+            };
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn reset_password(
+        cmd: ResetPassword,
+        context: AtomicContextManager,
+        password_hasher: Arc<dyn PasswordHasher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let token_hash = PasswordReset::hash_token(&cmd.token);
+            let mut auth_aggregate = match uow
+                .repository()
+                .get_by_password_reset_token_hash(&token_hash)
+                .await
+            {
+                Ok(auth_aggregate) => auth_aggregate,
+                Err(ApplicationError::EntityNotFound) => Err(ApplicationError::InvalidToken)?,
+                Err(err) => Err(err)?,
+            };
+            let hashed_password = password::hash_password(password_hasher, cmd.new_password).await?;
+            auth_aggregate.reset_password(&cmd.token, hashed_password)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn change_password(
+        cmd: ChangePassword,
+        context: AtomicContextManager,
+        password_hasher: Arc<dyn PasswordHasher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            let verification = password::verify_password(
+                password_hasher.clone(),
+                cmd.current_password,
+                auth_aggregate.account.hashed_password().to_string(),
+            )
+            .await?;
+            if verification == PasswordVerification::Invalid {
+                Err(ApplicationError::InvalidCredentials)?
+            }
+            let hashed_password = password::hash_password(password_hasher, cmd.new_password).await?;
+            auth_aggregate.change_password(verification, hashed_password)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn unblock_account(
+        cmd: UnblockAccount,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            auth_aggregate.unblock(cmd.moderator)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    /// Secret is handed out only here, in base32 and as provisioning URI for authenticator apps.
+    pub fn enable_totp(
+        cmd: EnableTotp,
+        context: AtomicContextManager,
+        secret_cipher: Arc<SecretCipher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            let secret = Totp::generate_secret();
+            let recovery_codes = auth_aggregate.enable_totp(secret_cipher.encrypt(&secret)?)?;
+            let totp = Totp::new(secret);
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+
+            Ok(TotpEnrollment {
+                secret: totp.encoded_secret(),
+                provisioning_uri: totp
+                    .provisioning_uri(&config().auth.totp_issuer, &auth_aggregate.account.email),
+                recovery_codes,
+            }
+            .try_into()?)
+        })
+    }
+
+    pub fn confirm_totp(
+        cmd: ConfirmTotp,
+        context: AtomicContextManager,
+        secret_cipher: Arc<SecretCipher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            let step = Self::totp_step(&auth_aggregate, &secret_cipher, &cmd.code)?;
+            auth_aggregate.confirm_totp(step)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn disable_totp(
+        cmd: DisableTotp,
+        context: AtomicContextManager,
+        secret_cipher: Arc<SecretCipher>,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            let step = Self::totp_step(&auth_aggregate, &secret_cipher, &cmd.code)?;
+            auth_aggregate.disable_totp(&cmd.code, step)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    /// Sessions not yet expired, oldest first.
+    pub fn list_sessions(cmd: ListSessions, context: AtomicContextManager) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            uow.commit().await?;
+
+            let sessions: Vec<SessionSummary> = auth_aggregate
+                .sessions
+                .into_iter()
+                .filter(|session| !session.is_expired())
+                .map(|session| SessionSummary {
+                    current: cmd.current_session_id == Some(session.id),
+                    id: session.id,
+                    user_agent: session.user_agent,
+                    ip: session.ip,
+                    create_dt: session.create_dt,
+                    last_seen_dt: session.last_seen_dt,
+                    expiry_date: session.expiry_date,
+                })
+                .collect();
+            Ok(sessions.try_into()?)
+        })
+    }
+
+    pub fn revoke_session(cmd: RevokeSession, context: AtomicContextManager) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            auth_aggregate.revoke_session(cmd.session_id)?;
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    pub fn revoke_all_sessions(
+        cmd: RevokeAllSessions,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let mut uow = UnitOfWork::<Repository<AuthAggregate>>::new(context.clone()).await?;
+
+            let mut auth_aggregate = uow.repository().get(&cmd.account_id).await?;
+            auth_aggregate.revoke_all_sessions();
+            uow.repository().update(&mut auth_aggregate).await?;
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    /// Notify event left as outbox externally, after which the outbox is tagged processed.
+    /// Delivery is at-least-once, as the outbox is left unprocessed when the transaction fails after delivery.
+    pub fn handle_outbox(
+        mut outbox: Outbox,
+        context: AtomicContextManager,
+        mailer: Arc<dyn Mailer>,
+    ) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let _msg = outbox.convert_event();
+            let msg = outbox.convert_event();
 
             let uow =
                 UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
                     .await?;
 
-            
-
-            // ! Todo msg handling logic
+            let from = &config().mail.from;
+            // * Token is delivered only by mail, so it is scrubbed from the outbox once sent.
+            if let Some(event) = msg.downcast_ref::<VerificationRequested>() {
+                mailer.send(Mail::verification(from, event)).await?;
+                outbox.scrub("token")?;
+            } else if let Some(event) = msg.downcast_ref::<PasswordResetRequested>() {
+                mailer.send(Mail::password_reset(from, event)).await?;
+                outbox.scrub("token")?;
+            } else if let Some(event) = msg.downcast_ref::<AccountLocked>() {
+                mailer.send(Mail::account_locked(from, event)).await?;
+            } else if let Some(event) = msg.downcast_ref::<AccountUnblocked>() {
+                mailer.send(Mail::account_unblocked(from, event)).await?;
+            }
 
             outbox.update(uow.executor()).await?;
 
@@ -111,10 +693,7 @@ impl EventHandler {
         some_dependency: fn(String, i32) -> ServiceResponse,
     ) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let mut uow =
-                UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
-                    .await?;
-            
+            let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
             println!("You got here!");
             uow.commit().await?;
 
@@ -126,14 +705,78 @@ impl EventHandler {
         context: AtomicContextManager,
     ) -> Future<ServiceResponse> {
         Box::pin(async move {
-            let mut uow =
-                UnitOfWork::<Repository<BoardAggregate>>::new(context.clone())
-                    .await?;
-            
+            let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
             println!("You got here too!");
             uow.commit().await?;
 
             Ok(ServiceResponse::Empty(()))
         })
     }
+
+    /// Start moderation process for the board created, issuing its moderation check step.
+    /// Process that is not moved on in 24 hours is timed out by the scheduler.
+    pub fn start_board_moderation(
+        event: BoardCreated,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
+
+            let correlation_id = event.id.to_string();
+            match Process::<BoardModeration>::get(uow.executor(), &correlation_id).await {
+                // * Moderation has already been started.
+                Ok(_process) => {
+                    uow.rollback().await?;
+                    return Ok(().into());
+                }
+                Err(ApplicationError::EntityNotFound) => (),
+                Err(err) => Err(err)?,
+            };
+
+            let mut process = Process::start(
+                correlation_id,
+                BoardModeration::new(event.id),
+                Some(chrono::Duration::hours(24)),
+            );
+            process.issue(
+                "check",
+                &CheckBoardModeration {
+                    board_id: event.id,
+                },
+            )?;
+            process.save(uow.executor()).await?;
+
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
+
+    /// Move moderation process on with the result of its check, compensating it on rejection.
+    pub fn advance_board_moderation(
+        event: BoardModerated,
+        context: AtomicContextManager,
+    ) -> Future<ServiceResponse> {
+        Box::pin(async move {
+            let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context.clone()).await?;
+
+            let mut process =
+                Process::<BoardModeration>::get(uow.executor(), &event.id.to_string()).await?;
+            // * Process that has already been completed, compensated or timed out is left as it is.
+            if process.status != ProcessStatus::Running {
+                uow.rollback().await?;
+                return Ok(().into());
+            }
+            if event.approved {
+                process.state.step = ModerationStep::Approved;
+                process.complete();
+            } else {
+                process.state.step = ModerationStep::Rejected;
+                process.compensate()?;
+            }
+            process.save(uow.executor()).await?;
+
+            uow.commit().await?;
+            Ok(().into())
+        })
+    }
 }
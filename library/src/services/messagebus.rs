@@ -1,15 +1,25 @@
-use tokio::sync::mpsc::error::TryRecvError;
+use futures::FutureExt;
+use serde::Serialize;
+use tokio::sync::{
+    mpsc::{error::TryRecvError, Receiver},
+    RwLock,
+};
 
 use crate::{
-    adapters::database::{AtomicContextManager, ContextManager},
-    bootstrap::{CommandHandler, EventHandler},
+    adapters::{
+        database::{AtomicContextManager, ContextManager, EventChannelConfig, Executor},
+        idempotency::Idempotency,
+        repositories::Repositories,
+    },
+    bootstrap::{connection_pool, CommandHandler, EventHandler},
     domain::{
         commands::{Command, ServiceResponse},
         AnyTrait, Message,
     },
+    services::{handlers::Future, unit_of_work::UnitOfWork},
     utils::{ApplicationError, ApplicationResult},
 };
-use std::sync::Arc;
+use std::{collections::VecDeque, panic::AssertUnwindSafe, sync::Arc};
 
 #[cfg(test)]
 use std::sync::atomic::AtomicI32;
@@ -20,12 +30,14 @@ pub struct MessageBus {
 
     command_handler: &'static CommandHandler<AtomicContextManager>,
     event_handler: &'static EventHandler<AtomicContextManager>,
+    channel_config: EventChannelConfig,
 }
 
 impl MessageBus {
     pub fn new(
         command_handler: &'static CommandHandler<AtomicContextManager>,
         event_handler: &'static EventHandler<AtomicContextManager>,
+        channel_config: EventChannelConfig,
     ) -> Arc<Self> {
         Self {
             #[cfg(test)]
@@ -33,6 +45,7 @@ impl MessageBus {
 
             command_handler,
             event_handler,
+            channel_config,
         }
         .into()
     }
@@ -41,24 +54,99 @@ impl MessageBus {
     where
         C: Command + AnyTrait,
     {
-        let (context_manager, mut event_receiver) = ContextManager::new().await;
+        self._handle(message, None).await
+    }
 
-        let res = self
-            .command_handler
-            .get(&message.type_id())
-            .ok_or_else(|| {
-                eprintln!("Unprocessable Command Given!");
-                ApplicationError::CommandNotFound
-            })?(message.as_any(), context_manager.clone())
-        .await?;
+    /// Handle command at most once per idempotency key.
+    /// The response to the first command is replayed for any later command sent with the same key,
+    /// while the same key sent with a different command is rejected as `IdempotencyConflict`.
+    pub async fn handle_idempotent<C>(
+        &self,
+        message: C,
+        idempotency_key: Option<String>,
+    ) -> ApplicationResult<ServiceResponse>
+    where
+        C: Command + AnyTrait + Serialize,
+    {
+        let Some(idempotency_key) = idempotency_key else {
+            return self.handle(message).await;
+        };
+        let idempotency = Idempotency::new(idempotency_key, &message)?;
+        let executor = Arc::new(RwLock::new(Executor::new(connection_pool().await)));
+        if let Some(res) = idempotency.replay(executor).await? {
+            return Ok(res);
+        }
+        self._handle(message, Some(idempotency)).await
+    }
+
+    async fn _handle<C>(
+        &self,
+        message: C,
+        idempotency: Option<Idempotency>,
+    ) -> ApplicationResult<ServiceResponse>
+    where
+        C: Command + AnyTrait,
+    {
+        let (handler_name, handler) =
+            self.command_handler
+                .get(&message.type_id())
+                .ok_or_else(|| {
+                    eprintln!("Unprocessable Command Given!");
+                    ApplicationError::CommandNotFound
+                })?;
+
+        // * Each attempt gets fresh context so that nothing raised in failed attempt is carried over.
+        let retry_policy = message.retry_policy();
+        let mut attempt = 1;
+        let (context_manager, mut event_receiver, mut events, res) = loop {
+            let (context_manager, mut event_receiver) = self.context(&message).await;
+            let mut events = VecDeque::new();
+
+            let run = || handler(message.as_any(), context_manager.clone());
+            let handling = async {
+                match idempotency.as_ref() {
+                    None => Self::isolate(handler_name, run).await,
+                    Some(idempotency) => {
+                        Self::isolate_idempotent(
+                            handler_name,
+                            run,
+                            idempotency,
+                            context_manager.clone(),
+                        )
+                        .await
+                    }
+                }
+            };
+            let res = Self::draining(handling, &mut event_receiver, &mut events).await;
+            match res {
+                Err(err) if err.is_retryable() && attempt < retry_policy.max_attempts => {
+                    tracing::warn!(
+                        handler = handler_name,
+                        attempt,
+                        max_attempts = retry_policy.max_attempts,
+                        error = %err,
+                        "retrying command"
+                    );
+                    drop(context_manager);
+                    tokio::time::sleep(retry_policy.backoff * attempt).await;
+                    attempt += 1;
+                }
+                res => break (context_manager, event_receiver, events, res?),
+            }
+        };
 
         'event_handling_loop: loop {
             // * use of try_recv is to stop blocking it when all events are drained.
-            match event_receiver.try_recv() {
+            let received = match events.pop_front() {
+                Some(msg) => Ok(msg),
+                None => event_receiver.try_recv(),
+            };
+            match received {
                 // * Logging!
                 Ok(msg) => {
+                    let handling = self.handle_event(msg, context_manager.clone());
                     if let Err(ApplicationError::EventNotFound) =
-                        self.handle_event(msg, context_manager.clone()).await
+                        Self::draining(handling, &mut event_receiver, &mut events).await
                     {
                         continue;
                     };
@@ -77,6 +165,20 @@ impl MessageBus {
         Ok(res)
     }
 
+    /// Context the command is handled in, configured as the command requires.
+    pub async fn context<C: Command>(
+        &self,
+        message: &C,
+    ) -> (AtomicContextManager, Receiver<Box<dyn Message>>) {
+        let (context_manager, event_receiver) =
+            ContextManager::with_channel_config(self.channel_config).await;
+        let mut context = context_manager.write().await;
+        context.transaction_options = message.transaction_options();
+        context.read_preference = message.read_preference();
+        drop(context);
+        (context_manager, event_receiver)
+    }
+
     async fn handle_event(
         &self,
         msg: Box<dyn Message>,
@@ -92,8 +194,12 @@ impl MessageBus {
                 ApplicationError::EventNotFound
             })?;
 
-        for handler in handlers.iter() {
-            match handler(msg.message_clone(), context_manager.clone()).await {
+        for (handler_name, handler) in handlers.iter() {
+            match Self::isolate(handler_name, || {
+                handler(msg.message_clone(), context_manager.clone())
+            })
+            .await
+            {
                 Err(ApplicationError::StopSentinel) => {
                     eprintln!("Stop Sentinel Reached!");
                     break;
@@ -115,6 +221,65 @@ impl MessageBus {
         drop(context_manager);
         Ok(())
     }
+
+    /// Run handling to completion while taking the events it sends off the channel, to be handled afterwards,
+    /// so that the unit of work waiting on the full channel under `BackpressurePolicy::Block` is never left waiting on the bus.
+    async fn draining<T>(
+        handling: impl std::future::Future<Output = T>,
+        event_receiver: &mut Receiver<Box<dyn Message>>,
+        events: &mut VecDeque<Box<dyn Message>>,
+    ) -> T {
+        tokio::pin!(handling);
+        loop {
+            tokio::select! {
+                res = &mut handling => break res,
+                Some(msg) = event_receiver.recv() => events.push_back(msg),
+            }
+        }
+    }
+
+    /// Run a handler so that a panic raised inside it is turned into `ApplicationError::HandlerPanicked`
+    /// instead of tearing down the task that handles the request.
+    /// The handler is invoked within the guarded future so panics on both its synchronous and asynchronous parts are caught.
+    /// `UnitOfWork` is owned by the handler future, so unwinding drops its uncommitted transaction, which rolls it back.
+    async fn isolate(
+        handler_name: &str,
+        handler: impl FnOnce() -> Future<ServiceResponse>,
+    ) -> ApplicationResult<ServiceResponse> {
+        AssertUnwindSafe(async move { handler().await })
+            .catch_unwind()
+            .await
+            .unwrap_or_else(|_| {
+                eprintln!("Handler Panicked! Handler:{}", handler_name);
+                Err(ApplicationError::HandlerPanicked(handler_name.into()))
+            })
+    }
+
+    /// Run a handler within a transaction begun beforehand, which units of work of the handler join,
+    /// and claim the idempotency key with the response in the same transaction before it commits.
+    /// Nothing is committed when the handler fails, so the key can be retried.
+    async fn isolate_idempotent(
+        handler_name: &str,
+        handler: impl FnOnce() -> Future<ServiceResponse>,
+        idempotency: &Idempotency,
+        context_manager: AtomicContextManager,
+    ) -> ApplicationResult<ServiceResponse> {
+        let uow = UnitOfWork::<Repositories>::new(context_manager).await?;
+        match Self::isolate(handler_name, handler).await {
+            Ok(res) => {
+                idempotency.claim(uow.executor(), &res).await?;
+                uow.commit().await?;
+                Ok(res)
+            }
+            Err(err) => {
+                // * Error of the handler is what is reported, even if the transaction fails to roll back.
+                if let Err(rollback_err) = uow.rollback().await {
+                    eprintln!("Rollback Failed! Error:{}", rollback_err);
+                }
+                Err(err)
+            }
+        }
+    }
 }
 
 // ----------------------------------------------------------------------- //
@@ -1,3 +1,4 @@
 pub mod handlers;
 pub mod messagebus;
+pub mod scheduler;
 pub mod unit_of_work;
@@ -0,0 +1,143 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::RwLock;
+
+use crate::{
+    adapters::{
+        database::Executor, outbox::Outbox, process::Process, scheduled_command::ScheduledCommand,
+    },
+    bootstrap::{config, connection_pool},
+    domain::{
+        board::{commands::*, process::BoardModeration},
+        commands::{Command, ServiceResponse},
+    },
+    utils::{ApplicationError, ApplicationResult},
+};
+
+use super::messagebus::MessageBus;
+
+macro_rules! dispatch_command {
+    ( $obj:expr, $bus:expr, $( $type: ty ), * ) => {
+        match $obj.command_type.as_str() {
+            $(command_type if command_type == <$type as Command>::NAME => {
+                let command = serde_json::from_str::<$type>($obj.payload.as_str())
+                    .map_err(|err| ApplicationError::DeserializationError(Box::new(err)))?;
+                $bus.handle_idempotent(command, Some($obj.idempotency_key())).await
+            },)*
+            _ => {
+                eprintln!("Such command not allowed to be scheduled: {}", $obj.command_type);
+                Err(ApplicationError::CommandNotFound)
+            }
+        }
+    };
+}
+
+/// Worker that dispatches due `ScheduledCommand`s through `MessageBus`.
+///
+/// Delivery is at-least-once: a command is tagged processed only after it is handled,
+/// so a crash in between dispatches it again on the next run.
+/// It is dispatched with `ScheduledCommand::idempotency_key`, though, so the command handled already is replayed rather than handled again.
+/// Each command is claimed and tagged in a transaction of its own, so concurrent workers never pick the same command.
+pub struct Scheduler;
+
+impl Scheduler {
+    async fn dispatch(
+        scheduled_command: &ScheduledCommand,
+        bus: &MessageBus,
+    ) -> ApplicationResult<ServiceResponse> {
+        // you can insert any number of desired type as long as it is schedulable command.
+        dispatch_command!(
+            scheduled_command,
+            bus,
+            CreateBoard,
+            EditBoard,
+            AddComment,
+            EditComment,
+            CheckBoardModeration,
+            TakeDownBoard
+        )
+    }
+
+    /// Dispatch every command due by now and return the number of commands successfully handled.
+    pub async fn dispatch_due_commands(bus: &MessageBus) -> ApplicationResult<usize> {
+        let relay = &config().relay;
+        let pool = connection_pool().await;
+        let due_ids =
+            ScheduledCommand::due_ids(Arc::new(RwLock::new(Executor::new(pool))), relay.batch_size)
+                .await?;
+
+        let mut dispatched = 0;
+        for id in due_ids {
+            let executor = Arc::new(RwLock::new(Executor::new(pool)));
+            executor.write().await.begin().await?;
+            let Some(mut scheduled_command) = ScheduledCommand::claim(executor.clone(), id).await?
+            else {
+                executor.write().await.rollback().await?;
+                continue;
+            };
+
+            match Self::dispatch(&scheduled_command, bus).await {
+                Ok(_res) => {
+                    scheduled_command.tag_processed();
+                    dispatched += 1;
+                }
+                Err(err) => {
+                    eprintln!(
+                        "Error Occurred While Dispatching Scheduled Command! Id:{} Error:{}",
+                        scheduled_command.id(),
+                        err
+                    );
+                    scheduled_command.tag_failed_attempt(relay.max_attempts);
+                }
+            }
+            scheduled_command.update(executor.clone()).await?;
+            executor.write().await.commit().await?;
+        }
+        Ok(dispatched)
+    }
+
+    /// Time out every process that is past its deadline, which issues its compensations.
+    /// Return the number of processes timed out.
+    pub async fn time_out_processes() -> ApplicationResult<usize> {
+        let executor = Arc::new(RwLock::new(Executor::new(connection_pool().await)));
+        executor.write().await.begin().await?;
+
+        // you can add any number of process managers to be timed out.
+        let timed_out = Process::<BoardModeration>::time_out_expired(executor.clone()).await?;
+
+        executor.write().await.commit().await?;
+        Ok(timed_out)
+    }
+
+    /// Hand every unprocessed outbox over to `MessageBus`, which notifies its event externally.
+    /// Return the number of outboxes successfully handled; the rest are retried on next run.
+    pub async fn relay_outboxes(bus: &MessageBus) -> ApplicationResult<usize> {
+        let executor = Arc::new(RwLock::new(Executor::new(connection_pool().await)));
+
+        let mut relayed = 0;
+        for outbox in Outbox::get(executor).await? {
+            match bus.handle(outbox).await {
+                Ok(_res) => relayed += 1,
+                Err(err) => eprintln!("Error Occurred While Relaying Outbox! Error:{}", err),
+            }
+        }
+        Ok(relayed)
+    }
+
+    /// Poll for expired processes, due commands and outboxes every `interval` until the task is dropped.
+    pub async fn run(bus: Arc<MessageBus>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(err) = Self::time_out_processes().await {
+                eprintln!("Process Timeout Failed! Error:{}", err);
+            }
+            if let Err(err) = Self::dispatch_due_commands(&bus).await {
+                eprintln!("Scheduler Run Failed! Error:{}", err);
+            }
+            if let Err(err) = Self::relay_outboxes(&bus).await {
+                eprintln!("Outbox Relay Failed! Error:{}", err);
+            }
+        }
+    }
+}
@@ -1,12 +1,20 @@
 
 
+use std::collections::VecDeque;
 use std::sync::Arc;
 
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::RwLock;
 
-use crate::adapters::database::{AtomicContextManager, Executor};
-use crate::adapters::repositories::TRepository;
+use crate::adapters::database::{
+    AtomicContextManager, BackpressurePolicy, Executor, OngoingTransaction, PendingEvents,
+    TransactionOptions,
+};
+use crate::adapters::repositories::{Repositories, Repository, TRepository};
 
+use crate::domain::auth::AuthAggregate;
+use crate::domain::board::BoardAggregate;
+use crate::domain::Message;
 use crate::utils::ApplicationError;
 use crate::{adapters::outbox::Outbox, utils::ApplicationResult};
 
@@ -18,24 +26,95 @@ where
     executor: Arc<RwLock<Executor>>,
     context: AtomicContextManager,
     pub repository: R,
-    
+
+    // * Savepoint that the unit of work is scoped to, when it is nested in another.
+    savepoint: Option<String>,
+    // * Events of nested units of work that have committed, processed when this one commits.
+    pending_events: PendingEvents,
+    parent_pending_events: Option<PendingEvents>,
+
+    // * Whether it has been committed or rolled back, which is checked on drop.
+    finished: bool,
 }
 impl<R> UnitOfWork<R>
 where
     R: TRepository,
     
 {
+    /// Begin unit of work on context. When another one is already in progress on the same context,
+    /// as when a handler invokes another, it joins that transaction as a nested unit of work.
     pub async fn new(context: AtomicContextManager) -> ApplicationResult<Self> {
-        let executor = context.read().await.executor();
+        Self::start(context, None).await
+    }
+    /// Begin unit of work with its own transaction options, overriding the ones set on context.
+    /// Options only apply when it begins the transaction; joining one already in progress, it takes that as is.
+    pub async fn with_options(
+        context: AtomicContextManager,
+        options: TransactionOptions,
+    ) -> ApplicationResult<Self> {
+        Self::start(context, Some(options)).await
+    }
+    async fn start(
+        context: AtomicContextManager,
+        options: Option<TransactionOptions>,
+    ) -> ApplicationResult<Self> {
+        let ongoing = context.read().await.ongoing_transaction().await;
+        if let Some(OngoingTransaction {
+            executor,
+            pending_events,
+        }) = ongoing
+        {
+            return Self::joining(context, executor, pending_events).await;
+        }
+
+        let executor = match options {
+            Some(options) => context.read().await.executor_with_options(options),
+            None => context.read().await.executor(),
+        };
         let mut uow = Self {
             repository: R::new(executor.clone()),
             context,
             executor,
-            
+            savepoint: None,
+            pending_events: Default::default(),
+            parent_pending_events: None,
+            finished: false,
         };
         uow.begin().await?;
+        uow.context.write().await.ongoing_transaction = Some(OngoingTransaction {
+            executor: uow.executor.clone(),
+            pending_events: uow.pending_events.clone(),
+        });
         Ok(uow)
     }
+
+    /// Begin unit of work nested in this one, which shares the transaction but is scoped to a savepoint.
+    /// Rolling it back undoes only what has been done within it and discards the events it collected,
+    /// whereas committing it hands its events over to this one, to be processed when this one commits.
+    pub async fn nested<N: TRepository>(&self) -> ApplicationResult<UnitOfWork<N>> {
+        UnitOfWork::joining(
+            self.context.clone(),
+            self.executor.clone(),
+            self.pending_events.clone(),
+        )
+        .await
+    }
+    async fn joining(
+        context: AtomicContextManager,
+        executor: Arc<RwLock<Executor>>,
+        parent_pending_events: PendingEvents,
+    ) -> ApplicationResult<Self> {
+        let savepoint = executor.write().await.savepoint().await?;
+        Ok(Self {
+            repository: R::new(executor.clone()),
+            context,
+            executor,
+            savepoint: Some(savepoint),
+            pending_events: Default::default(),
+            parent_pending_events: Some(parent_pending_events),
+            finished: false,
+        })
+    }
     pub fn repository(&mut self) -> &mut R {
         &mut self.repository
     }
@@ -52,9 +131,25 @@ where
     pub async fn commit(mut self) -> ApplicationResult<()> {
         // To drop uow itself!
 
+        if let Some(savepoint) = self.savepoint.clone() {
+            self.executor
+                .write()
+                .await
+                .release_savepoint(&savepoint)
+                .await?;
+            let mut events = self.collect_events().await;
+            if let Some(parent_pending_events) = self.parent_pending_events.as_ref() {
+                parent_pending_events.write().await.append(&mut events);
+            }
+            self.finished = true;
+            return Ok(());
+        }
+
         self._commit_hook().await?;
 
-        self._commit().await
+        self._commit().await?;
+        self.finished = true;
+        Ok(())
     }
     async fn _commit(&mut self) -> ApplicationResult<()> {
         let mut executor = self.executor.write().await;
@@ -62,32 +157,101 @@ where
         executor.commit().await
     }
 
-    pub async fn rollback(self) -> ApplicationResult<()> {
+    pub async fn rollback(mut self) -> ApplicationResult<()> {
         let mut executor = self.executor.write().await;
-        executor.rollback().await
+        match self.savepoint.as_ref() {
+            Some(savepoint) => executor.rollback_to_savepoint(savepoint).await?,
+            None => executor.rollback().await?,
+        };
+        drop(executor);
+        self.finished = true;
+        Ok(())
+    }
+
+    /// Events handed over by committed nested units of work, followed by the ones collected by the repository.
+    async fn collect_events(&mut self) -> VecDeque<Box<dyn Message>> {
+        let mut events = std::mem::take(&mut *self.pending_events.write().await);
+        events.append(&mut self.repository.get_events());
+        events
     }
 
     /// commit_hook is invoked right before the calling for commit
     /// which sorts out and processes outboxes and internally processable events.
     pub async fn _commit_hook(&mut self) -> ApplicationResult<()> {
-        let event_sender = &mut self.context.write().await.sender;
+        let events = self.collect_events().await;
+        let context = self.context.read().await;
         let mut outboxes = vec![];
 
-        for e in self.repository.get_events() {
+        for e in events {
             if e.externally_notifiable() {
                 outboxes.push(e.outbox());
             };
             if e.internally_notifiable() {
-                event_sender
-                    .send(e.message_clone())
-                    .await
-                    .expect("Event Collecting failed!")
+                match context.backpressure_policy {
+                    BackpressurePolicy::Block => context
+                        .sender
+                        .send(e.message_clone())
+                        .await
+                        .map_err(|_| ApplicationError::EventChannelClosed)?,
+                    BackpressurePolicy::Error => {
+                        context.sender.try_send(e.message_clone()).map_err(|err| {
+                            eprintln!("Event Collecting failed! {:?}", e);
+                            match err {
+                                TrySendError::Full(_) => ApplicationError::EventChannelFull,
+                                TrySendError::Closed(_) => ApplicationError::EventChannelClosed,
+                            }
+                        })?
+                    }
+                    BackpressurePolicy::Spill => match context.sender.try_send(e.message_clone()) {
+                        Ok(()) => (),
+                        // * Event that is externally notifiable has already been left as outbox.
+                        Err(TrySendError::Full(_)) if e.externally_notifiable() => (),
+                        Err(TrySendError::Full(_)) => {
+                            let outbox = e.outbox();
+                            if outbox.try_convert_event().is_none() {
+                                eprintln!("Event Not Spillable To Outbox! {:?}", e);
+                                Err(ApplicationError::EventChannelFull)?
+                            }
+                            outboxes.push(outbox);
+                        }
+                        Err(TrySendError::Closed(_)) => Err(ApplicationError::EventChannelClosed)?,
+                    },
+                }
             }
         }
         Outbox::add(self.executor(), outboxes).await
     }
 }
 
+/// Unit of work dropped without being committed or rolled back, as when the handler returns early on error,
+/// gives up on its transaction so that nothing done within it is committed.
+impl<R> Drop for UnitOfWork<R>
+where
+    R: TRepository,
+{
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        eprintln!("Unit Of Work Dropped Without Commit Or Rollback! Rolling Back...");
+        match self.executor.try_write() {
+            Ok(mut executor) => executor.abandon(self.savepoint.as_deref()),
+            Err(_) => {
+                eprintln!("Executor In Use! Transaction Is Rolled Back When Executor Is Dropped.")
+            }
+        }
+    }
+}
+
+impl UnitOfWork<Repositories> {
+    pub fn boards(&mut self) -> &mut Repository<BoardAggregate> {
+        &mut self.repository.boards
+    }
+    pub fn accounts(&mut self) -> &mut Repository<AuthAggregate> {
+        &mut self.repository.accounts
+    }
+}
+
 //TODO Using UOW, transaction handling
 #[cfg(test)]
 mod test_unit_of_work {
@@ -6,6 +6,7 @@ pub type AnyError = dyn error::Error + Send + Sync;
 pub enum ApplicationError {
     DatabaseConnectionError(Box<AnyError>),
     DeserializationError(Box<AnyError>),
+    SerializationError(Box<AnyError>),
     EntityNotFound,
     EventNotFound,
     CommandNotFound,
@@ -13,15 +14,50 @@ pub enum ApplicationError {
     TransactionError,
     ParsingError,
     StopSentinel,
+    HandlerPanicked(String),
+    EventChannelFull,
+    EventChannelClosed,
+    IdempotencyConflict,
+    ConcurrencyConflict,
+    InvalidConfig(String),
+    SchemaDrift(String),
+    EntityAlreadyExists(String),
+    InvalidCredentials,
+    InvalidToken,
+    TokenReused,
+    Forbidden,
+    PasswordHashingError(String),
+    AccountNotVerified,
+    MailDeliveryError(String),
+    TooManyRequests,
+    AccountLocked,
+    SecretEncryptionError(String),
 }
 
 impl error::Error for ApplicationError {}
 
+impl ApplicationError {
+    /// Whether handling the command again from scratch may succeed,
+    /// as in version conflict, serialization failure(40001) and deadlock(40P01).
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ApplicationError::ConcurrencyConflict => true,
+            ApplicationError::DatabaseConnectionError(err) => matches!(
+                err.downcast_ref::<sqlx::Error>(),
+                Some(sqlx::Error::Database(db_err))
+                    if matches!(db_err.code().as_deref(), Some("40001") | Some("40P01"))
+            ),
+            _ => false,
+        }
+    }
+}
+
 impl Display for ApplicationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ApplicationError::DatabaseConnectionError(res) => write!(f, "{}", res),
             ApplicationError::DeserializationError(res) => write!(f, "{}", res),
+            ApplicationError::SerializationError(res) => write!(f, "{}", res),
             ApplicationError::EntityNotFound => write!(f, "EntityNotFound"),
             ApplicationError::CommandNotFound => write!(f, "CommandNotFound"),
             ApplicationError::EventNotFound => write!(f, "EventNotFound"),
@@ -29,6 +65,32 @@ impl Display for ApplicationError {
             ApplicationError::TransactionError => write!(f, "TransactionError"),
             ApplicationError::StopSentinel => write!(f, "StopSentinel"),
             ApplicationError::ParsingError => write!(f, "ParsingError"),
+            ApplicationError::HandlerPanicked(handler) => {
+                write!(f, "HandlerPanicked: {}", handler)
+            }
+            ApplicationError::EventChannelFull => write!(f, "EventChannelFull"),
+            ApplicationError::EventChannelClosed => write!(f, "EventChannelClosed"),
+            ApplicationError::IdempotencyConflict => write!(f, "IdempotencyConflict"),
+            ApplicationError::ConcurrencyConflict => write!(f, "ConcurrencyConflict"),
+            ApplicationError::InvalidConfig(problem) => write!(f, "InvalidConfig: {}", problem),
+            ApplicationError::SchemaDrift(drift) => write!(f, "SchemaDrift: {}", drift),
+            ApplicationError::EntityAlreadyExists(field) => {
+                write!(f, "EntityAlreadyExists: {}", field)
+            }
+            ApplicationError::InvalidCredentials => write!(f, "InvalidCredentials"),
+            ApplicationError::InvalidToken => write!(f, "InvalidToken"),
+            ApplicationError::TokenReused => write!(f, "TokenReused"),
+            ApplicationError::Forbidden => write!(f, "Forbidden"),
+            ApplicationError::PasswordHashingError(err) => {
+                write!(f, "PasswordHashingError: {}", err)
+            }
+            ApplicationError::AccountNotVerified => write!(f, "AccountNotVerified"),
+            ApplicationError::MailDeliveryError(err) => write!(f, "MailDeliveryError: {}", err),
+            ApplicationError::TooManyRequests => write!(f, "TooManyRequests"),
+            ApplicationError::AccountLocked => write!(f, "AccountLocked"),
+            ApplicationError::SecretEncryptionError(err) => {
+                write!(f, "SecretEncryptionError: {}", err)
+            }
         }
     }
 }
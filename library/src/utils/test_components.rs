@@ -1,14 +1,19 @@
 #[cfg(test)]
 pub mod components {
 
+    use std::sync::Once;
+
     use dotenv::dotenv;
     use futures::Future;
 
-    use crate::bootstrap::connection_pool;
+    use crate::bootstrap::{connection_pool, Boostrap};
+    use crate::config::Config;
+
+    static CONFIGURE: Once = Once::new();
 
     pub async fn tear_down() {
         let pool = connection_pool().await;
-        sqlx::query("TRUNCATE community_board, community_comment, auth_account, auth_token_stat,service_outbox")
+        sqlx::query("TRUNCATE community_board, community_comment, auth_account, auth_token_stat, auth_email_verification, auth_password_reset, auth_login_attempt, auth_totp, service_outbox, service_scheduled_command, service_process, service_idempotency")
             .execute(pool)
             .await
             .unwrap();
@@ -19,6 +24,9 @@ pub mod components {
         T: Future<Output = ()>,
     {
         dotenv().unwrap();
+        CONFIGURE.call_once(|| {
+            Boostrap::configure(Config::load().unwrap()).expect("Configuration set failed!")
+        });
         Box::pin(test).await;
         tear_down().await;
     }
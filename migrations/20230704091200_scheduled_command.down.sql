@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS service_scheduled_command;
+
+DROP TYPE IF EXISTS scheduled_command_state;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TYPE scheduled_command_state AS ENUM (
+    'Pending', 'Processed', 'Canceled', 'Failed'
+);
+
+CREATE TABLE IF NOT EXISTS service_scheduled_command(
+    id UUID PRIMARY KEY,
+    command_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    due_dt TIMESTAMPTZ NOT NULL,
+    dedupe_key TEXT UNIQUE,
+    state scheduled_command_state NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    create_dt TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_service_scheduled_command_due
+    ON service_scheduled_command (due_dt)
+    WHERE state = 'Pending';
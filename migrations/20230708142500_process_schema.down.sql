@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS service_process;
+
+DROP TYPE IF EXISTS process_status;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+CREATE TYPE process_status AS ENUM (
+    'Running', 'Completed', 'Compensated', 'TimedOut'
+);
+
+CREATE TABLE IF NOT EXISTS service_process(
+    process_type TEXT NOT NULL,
+    correlation_id TEXT NOT NULL,
+    status process_status NOT NULL,
+    state TEXT NOT NULL,
+    deadline TIMESTAMPTZ,
+    version INTEGER NOT NULL DEFAULT 0,
+    create_dt TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (process_type, correlation_id)
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE auth_account DROP CONSTRAINT IF EXISTS auth_account_nickname_key;
+ALTER TABLE auth_account DROP CONSTRAINT IF EXISTS auth_account_email_key;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE auth_account ADD CONSTRAINT auth_account_email_key UNIQUE (email);
+ALTER TABLE auth_account ADD CONSTRAINT auth_account_nickname_key UNIQUE (nickname);
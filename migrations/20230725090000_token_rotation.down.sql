@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS auth_token_stat_rotated_refresh_token_hashes_idx;
+DROP INDEX IF EXISTS auth_token_stat_refresh_token_hash_idx;
+
+ALTER TABLE auth_token_stat DROP COLUMN IF EXISTS rotated_refresh_token_hashes;
+ALTER TABLE auth_token_stat RENAME COLUMN refresh_token_hash TO refresh_token;
+ALTER TABLE auth_token_stat ADD COLUMN access_token TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,9 @@
+-- Add up migration script here
+
+-- * Access token is self-contained JWT, so it is not kept.
+ALTER TABLE auth_token_stat DROP COLUMN IF EXISTS access_token;
+ALTER TABLE auth_token_stat RENAME COLUMN refresh_token TO refresh_token_hash;
+ALTER TABLE auth_token_stat ADD COLUMN rotated_refresh_token_hashes TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE UNIQUE INDEX IF NOT EXISTS auth_token_stat_refresh_token_hash_idx ON auth_token_stat (refresh_token_hash);
+CREATE INDEX IF NOT EXISTS auth_token_stat_rotated_refresh_token_hashes_idx ON auth_token_stat USING GIN (rotated_refresh_token_hashes);
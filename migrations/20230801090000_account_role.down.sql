@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE auth_account DROP COLUMN IF EXISTS role;
+
+DROP TYPE IF EXISTS account_role;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TYPE account_role AS ENUM (
+    'Member', 'Moderator'
+);
+
+ALTER TABLE auth_account ADD COLUMN role account_role NOT NULL DEFAULT 'Member';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS auth_email_verification_token_hash_idx;
+
+DROP TABLE IF EXISTS auth_email_verification;
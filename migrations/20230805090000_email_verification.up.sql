@@ -0,0 +1,14 @@
+-- Add up migration script here
+
+-- * Token is kept hashed, and the row is deleted once the token is used.
+CREATE TABLE IF NOT EXISTS auth_email_verification(
+    account_id TEXT PRIMARY KEY,
+    token_hash TEXT NOT NULL,
+    expiry_date TIMESTAMPTZ NOT NULL,
+    CONSTRAINT fk_account_id
+        FOREIGN KEY(account_id)
+        REFERENCES auth_account(id)
+        ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS auth_email_verification_token_hash_idx ON auth_email_verification (token_hash);
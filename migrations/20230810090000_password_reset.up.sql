@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+-- * Token is kept hashed, and the row is deleted once the token is used.
+-- * `requested_dt` is kept so that reset can't be requested again too soon.
+CREATE TABLE IF NOT EXISTS auth_password_reset(
+    account_id TEXT PRIMARY KEY,
+    token_hash TEXT NOT NULL,
+    expiry_date TIMESTAMPTZ NOT NULL,
+    requested_dt TIMESTAMPTZ NOT NULL,
+    CONSTRAINT fk_account_id
+        FOREIGN KEY(account_id)
+        REFERENCES auth_account(id)
+        ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS auth_password_reset_token_hash_idx ON auth_password_reset (token_hash);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE auth_account DROP COLUMN IF EXISTS locked_until;
+
+DROP INDEX IF EXISTS auth_login_attempt_account_id_create_dt_idx;
+
+DROP TABLE IF EXISTS auth_login_attempt;
@@ -0,0 +1,20 @@
+-- Add up migration script here
+
+-- * Account is absent when login is attempted with unknown email.
+CREATE TABLE IF NOT EXISTS auth_login_attempt(
+    id UUID PRIMARY KEY,
+    account_id TEXT,
+    email TEXT NOT NULL,
+    ip TEXT,
+    user_agent TEXT,
+    succeeded BOOLEAN NOT NULL,
+    create_dt TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_account_id
+        FOREIGN KEY(account_id)
+        REFERENCES auth_account(id)
+        ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS auth_login_attempt_account_id_create_dt_idx ON auth_login_attempt (account_id, create_dt);
+
+ALTER TABLE auth_account ADD COLUMN locked_until TIMESTAMPTZ;
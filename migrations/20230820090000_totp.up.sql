@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+-- * Secret is kept encrypted, as it must be read back to check codes, while recovery codes are kept hashed.
+-- * `last_used_step` is the time step of the last code accepted, so that a code is not accepted twice.
+CREATE TABLE IF NOT EXISTS auth_totp(
+    account_id TEXT PRIMARY KEY,
+    encrypted_secret TEXT NOT NULL,
+    confirmed BOOLEAN NOT NULL DEFAULT FALSE,
+    recovery_code_hashes TEXT[] NOT NULL DEFAULT '{}',
+    last_used_step BIGINT NOT NULL DEFAULT 0,
+    create_dt TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_account_id
+        FOREIGN KEY(account_id)
+        REFERENCES auth_account(id)
+        ON DELETE CASCADE
+);
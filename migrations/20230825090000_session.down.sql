@@ -0,0 +1,16 @@
+-- Add down migration script here
+DROP INDEX IF EXISTS auth_token_stat_account_id_idx;
+
+ALTER TABLE auth_token_stat ALTER COLUMN create_dt SET DEFAULT NOW() + INTERVAL '30 minutes';
+ALTER TABLE auth_token_stat DROP COLUMN IF EXISTS last_seen_dt;
+ALTER TABLE auth_token_stat DROP COLUMN IF EXISTS ip;
+ALTER TABLE auth_token_stat DROP COLUMN IF EXISTS user_agent;
+
+-- * Only the latest session of each account is kept, as an account can have only one.
+DELETE FROM auth_token_stat stale USING auth_token_stat latest
+WHERE stale.account_id = latest.account_id
+    AND (stale.create_dt, stale.id) < (latest.create_dt, latest.id);
+
+ALTER TABLE auth_token_stat DROP CONSTRAINT IF EXISTS auth_token_stat_pkey;
+ALTER TABLE auth_token_stat DROP COLUMN IF EXISTS id;
+ALTER TABLE auth_token_stat ADD PRIMARY KEY (account_id);
@@ -0,0 +1,15 @@
+-- Add up migration script here
+
+-- * Each row is a session on a device, so an account has as many as the devices it is logged in from.
+-- * `last_seen_dt` is updated on refresh rather than on every request.
+ALTER TABLE auth_token_stat DROP CONSTRAINT IF EXISTS auth_token_stat_pkey;
+ALTER TABLE auth_token_stat ADD COLUMN id UUID NOT NULL DEFAULT gen_random_uuid();
+ALTER TABLE auth_token_stat ADD PRIMARY KEY (id);
+ALTER TABLE auth_token_stat ALTER COLUMN id DROP DEFAULT;
+
+ALTER TABLE auth_token_stat ADD COLUMN user_agent TEXT;
+ALTER TABLE auth_token_stat ADD COLUMN ip TEXT;
+ALTER TABLE auth_token_stat ADD COLUMN last_seen_dt TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE auth_token_stat ALTER COLUMN create_dt SET DEFAULT NOW();
+
+CREATE INDEX IF NOT EXISTS auth_token_stat_account_id_idx ON auth_token_stat (account_id);
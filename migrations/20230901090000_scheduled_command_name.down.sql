@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+-- * Only board commands can be scheduled, so their path is restored.
+UPDATE service_scheduled_command
+SET command_type = 'library::domain::board::commands::' || command_type
+WHERE command_type NOT LIKE '%::%';
@@ -0,0 +1,6 @@
+-- Add up migration script here
+
+-- * Command type is now the stable name of the command rather than its Rust path, which changes across refactors.
+UPDATE service_scheduled_command
+SET command_type = regexp_replace(command_type, '^.*::', '')
+WHERE command_type LIKE '%::%';
@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE auth_email_verification DROP COLUMN IF EXISTS requested_dt;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+
+-- * `requested_dt` is kept so that verification can't be requested again too soon.
+ALTER TABLE auth_email_verification ADD COLUMN requested_dt TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE auth_email_verification ALTER COLUMN requested_dt DROP DEFAULT;
@@ -0,0 +1,427 @@
+mod helpers;
+
+#[cfg(test)]
+mod auth_tests {
+    use std::sync::Arc;
+
+    use crate::helpers::functions::*;
+    use chrono::Duration;
+    use library::adapters::database::ContextManager;
+    use library::adapters::jwt::AccessToken;
+    use library::adapters::mailer::InMemoryMailer;
+    use library::adapters::outbox::Outbox;
+    use library::adapters::password::{Argon2Hasher, PasswordHasher};
+    use library::bootstrap::{connection_pool, Boostrap};
+    use library::domain::auth::commands::{
+        IssuedToken, RefreshToken, RequestVerification, VerifyEmail,
+    };
+    use library::domain::auth::entity::{
+        AccountRole, AccountState, EmailVerification, TokenStat, MAX_ROTATED_REFRESH_TOKEN_HASHES,
+    };
+    use library::domain::commands::ServiceResponse;
+    use library::services::handlers::ServiceHandler;
+    use library::utils::ApplicationError;
+
+    #[tokio::test]
+    async fn test_register_account() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+
+            '_test_code: {
+                let Ok(ServiceResponse::String(id)) = bus
+                    .handle(register_account_cmd("migo@mail.com", "Migo"))
+                    .await
+                else {
+                    panic!("Account must be registered!")
+                };
+
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                let auth_aggregate = account_repository_helper(executor).get(&id).await.unwrap();
+                assert_eq!(auth_aggregate.account.email, "migo@mail.com");
+                assert_eq!(auth_aggregate.account.nickname, "Migo");
+                assert_eq!(
+                    auth_aggregate.account.state,
+                    AccountState::VerificationRequired
+                );
+                assert!(auth_aggregate.verification.is_some());
+
+                // * Password is hashed with Argon2id by default.
+                let hashed_password = auth_aggregate.account.hashed_password();
+                assert!(hashed_password.starts_with("$argon2id$"));
+                let hasher = Argon2Hasher::new(19 * 1024, 2, 1).unwrap();
+                assert!(hasher.verify("testpass", hashed_password));
+                assert!(!hasher.needs_rehash(hashed_password));
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_register_account_with_taken_email_or_nickname() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            bus.handle(register_account_cmd("mago@mail.com", "Mago"))
+                .await
+                .unwrap();
+
+            '_test_code: {
+                let Err(ApplicationError::EntityAlreadyExists(field)) = bus
+                    .handle(register_account_cmd("mago@mail.com", "Another"))
+                    .await
+                else {
+                    panic!("Email taken must conflict!")
+                };
+                assert_eq!(field, "email");
+
+                let Err(ApplicationError::EntityAlreadyExists(field)) = bus
+                    .handle(register_account_cmd("another@mail.com", "Mago"))
+                    .await
+                else {
+                    panic!("Nickname taken must conflict!")
+                };
+                assert_eq!(field, "nickname");
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_login() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(register_account_cmd("login@mail.com", "Login"))
+                .await
+            else {
+                panic!("Account must be registered!")
+            };
+
+            '_test_code: {
+                let token: IssuedToken = bus
+                    .handle(login_cmd("login@mail.com", "testpass"))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert_eq!(token.token_type, "Bearer");
+                let claims = AccessToken::verify(&token.access_token).unwrap();
+                assert_eq!(claims.sub, id);
+                assert_eq!(
+                    AccessToken::verify_active(&token.access_token)
+                        .await
+                        .unwrap(),
+                    claims
+                );
+                assert_eq!(claims.role, AccountRole::Member);
+                assert!(AccessToken::verify("forged.token.value").is_err());
+
+                // * Only hash of refresh token is stored.
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                let auth_aggregate = account_repository_helper(executor).get(&id).await.unwrap();
+                let session = auth_aggregate.sessions.first().unwrap();
+                assert_eq!(session.id, claims.sid);
+                assert_ne!(session.refresh_token_hash, token.refresh_token);
+
+                for cmd in [
+                    login_cmd("login@mail.com", "wrongpass"),
+                    login_cmd("unknown@mail.com", "testpass"),
+                ] {
+                    let Err(ApplicationError::InvalidCredentials) = bus.handle(cmd).await else {
+                        panic!("Login must fail on wrong credentials!")
+                    };
+                }
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_login_upgrades_legacy_bcrypt_hash() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut account_repo = account_repository_helper(executor.clone());
+
+            let mut auth_aggregate = account_create_helper();
+            executor.write().await.begin().await.unwrap();
+            let id = account_repo.add(&mut auth_aggregate).await.unwrap();
+            executor.write().await.commit().await.unwrap();
+            let email = auth_aggregate.account.email.clone();
+
+            '_test_code: {
+                let Err(ApplicationError::InvalidCredentials) =
+                    bus.handle(login_cmd(&email, "wrongpass")).await
+                else {
+                    panic!("Login must fail on wrong password!")
+                };
+                bus.handle(login_cmd(&email, "testpass")).await.unwrap();
+
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                let upgraded = account_repository_helper(executor).get(&id).await.unwrap();
+                assert!(upgraded.account.hashed_password().starts_with("$argon2id$"));
+                assert_eq!(upgraded.account.version, 1);
+
+                // * Upgraded hash keeps accepting the same password.
+                bus.handle(login_cmd(&email, "testpass")).await.unwrap();
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_rotation_and_reuse_detection() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            bus.handle(register_account_cmd("refresh@mail.com", "Refresh"))
+                .await
+                .unwrap();
+            let first: IssuedToken = bus
+                .handle(login_cmd("refresh@mail.com", "testpass"))
+                .await
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            '_test_code: {
+                let second: IssuedToken = bus
+                    .handle(refresh_token_cmd(&first))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert_ne!(first.refresh_token, second.refresh_token);
+
+                let Err(ApplicationError::InvalidToken) = bus
+                    .handle(RefreshToken {
+                        refresh_token: "unknown".into(),
+                    })
+                    .await
+                else {
+                    panic!("Unknown refresh token must be rejected!")
+                };
+
+                // * Refresh token rotated out is presented again.
+                let Err(ApplicationError::TokenReused) =
+                    bus.handle(refresh_token_cmd(&first)).await
+                else {
+                    panic!("Reuse must be detected!")
+                };
+
+                // * The whole family is revoked, including the latest one.
+                let Err(ApplicationError::InvalidToken) =
+                    bus.handle(refresh_token_cmd(&second)).await
+                else {
+                    panic!("Token family must be revoked on reuse!")
+                };
+            }
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_rotated_refresh_token_hashes_are_capped() {
+        let (mut token_stat, first) = TokenStat::create_token(Duration::days(1), None, None);
+        let mut token = token_stat.rotate(&first).unwrap();
+        for _ in 0..MAX_ROTATED_REFRESH_TOKEN_HASHES {
+            token = token_stat.rotate(&token).unwrap();
+        }
+        assert_eq!(
+            token_stat.rotated_refresh_token_hashes.len(),
+            MAX_ROTATED_REFRESH_TOKEN_HASHES
+        );
+
+        // * The oldest one has been forgotten, so it is no longer told apart from unknown token.
+        let Err(ApplicationError::InvalidToken) = token_stat.rotate(&first) else {
+            panic!("Forgotten refresh token must be rejected!")
+        };
+    }
+
+    #[tokio::test]
+    async fn test_email_verification() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(register_account_cmd("verify@mail.com", "Verify"))
+                .await
+            else {
+                panic!("Account must be registered!")
+            };
+
+            '_test_code: {
+                // * Verification token is delivered by mail once outbox is relayed.
+                let mailer = Arc::new(InMemoryMailer::default());
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                for outbox in Outbox::get(executor.clone()).await.unwrap() {
+                    ServiceHandler::handle_outbox(outbox, context_manager.clone(), mailer.clone())
+                        .await
+                        .unwrap();
+                }
+                let mails = mailer.take_sent();
+                let Some(mail) = mails.iter().find(|mail| mail.to == "verify@mail.com") else {
+                    panic!("Verification mail must be sent!")
+                };
+                let token = mail.body.lines().rfind(|line| !line.is_empty()).unwrap();
+                assert!(Outbox::get(executor.clone()).await.unwrap().is_empty());
+
+                // * Token delivered is not kept in plain.
+                let states = sqlx::query_scalar::<_, String>(
+                    "SELECT state FROM service_outbox WHERE topic = 'VerificationRequested'",
+                )
+                .fetch_all(connection_pool().await)
+                .await
+                .unwrap();
+                assert!(!states.is_empty());
+                assert!(states.iter().all(|state| !state.contains(token)));
+
+                let unverified: IssuedToken = bus
+                    .handle(login_cmd("verify@mail.com", "testpass"))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert!(
+                    !AccessToken::verify(&unverified.access_token)
+                        .unwrap()
+                        .verified
+                );
+
+                bus.handle(VerifyEmail {
+                    token: token.into(),
+                })
+                .await
+                .unwrap();
+                let auth_aggregate = account_repository_helper(executor).get(&id).await.unwrap();
+                assert_eq!(auth_aggregate.account.state, AccountState::Created);
+                assert!(auth_aggregate.verification.is_none());
+
+                // * Token is single-use.
+                let Err(ApplicationError::InvalidToken) = bus
+                    .handle(VerifyEmail {
+                        token: token.into(),
+                    })
+                    .await
+                else {
+                    panic!("Used token must be rejected!")
+                };
+
+                let verified: IssuedToken = bus
+                    .handle(login_cmd("verify@mail.com", "testpass"))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert!(
+                    AccessToken::verify(&verified.access_token)
+                        .unwrap()
+                        .verified
+                );
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_verification_requested_again() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            bus.handle(register_account_cmd("resend@mail.com", "Resend"))
+                .await
+                .unwrap();
+            let mailer = Arc::new(InMemoryMailer::default());
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let relay = || async {
+                for outbox in Outbox::get(executor.clone()).await.unwrap() {
+                    ServiceHandler::handle_outbox(outbox, context_manager.clone(), mailer.clone())
+                        .await
+                        .unwrap();
+                }
+                mailer
+                    .take_sent()
+                    .into_iter()
+                    .filter(|mail| mail.to == "resend@mail.com")
+                    .map(|mail| {
+                        mail.body
+                            .lines()
+                            .rfind(|line| !line.is_empty())
+                            .unwrap()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let first_token = relay().await.pop().unwrap();
+            let resend_cmd = |email: &str| RequestVerification {
+                email: email.into(),
+            };
+
+            '_test_code: {
+                // * Request made too soon, or for unknown email, is not told apart from success.
+                bus.handle(resend_cmd("resend@mail.com")).await.unwrap();
+                bus.handle(resend_cmd("unknown@mail.com")).await.unwrap();
+                assert!(relay().await.is_empty());
+
+                sqlx::query(
+                    "UPDATE auth_email_verification SET requested_dt = NOW() - INTERVAL '1 hour'",
+                )
+                .execute(connection_pool().await)
+                .await
+                .unwrap();
+                bus.handle(resend_cmd("resend@mail.com")).await.unwrap();
+                let second_token = relay().await.pop().unwrap();
+                assert_ne!(first_token, second_token);
+
+                // * Token issued again replaces the one before.
+                let Err(ApplicationError::InvalidToken) =
+                    bus.handle(VerifyEmail { token: first_token }).await
+                else {
+                    panic!("Replaced token must be rejected!")
+                };
+                bus.handle(VerifyEmail {
+                    token: second_token,
+                })
+                .await
+                .unwrap();
+
+                // * Verified account is given no token.
+                bus.handle(resend_cmd("resend@mail.com")).await.unwrap();
+                assert!(relay().await.is_empty());
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_expired_verification_token_is_rejected() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut account_repo = account_repository_helper(executor.clone());
+
+            let (verification, token) = EmailVerification::create_token(Duration::seconds(-1));
+            let mut auth_aggregate = account_create_helper();
+            auth_aggregate.verification = Some(verification);
+            executor.write().await.begin().await.unwrap();
+            let id = account_repo.add(&mut auth_aggregate).await.unwrap();
+            executor.write().await.commit().await.unwrap();
+
+            '_test_code: {
+                let Err(ApplicationError::InvalidToken) = bus.handle(VerifyEmail { token }).await
+                else {
+                    panic!("Expired token must be rejected!")
+                };
+                let auth_aggregate = account_repository_helper(executor).get(&id).await.unwrap();
+                assert_eq!(
+                    auth_aggregate.account.state,
+                    AccountState::VerificationRequired
+                );
+            }
+        })
+        .await;
+    }
+}
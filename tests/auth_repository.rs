@@ -0,0 +1,263 @@
+mod helpers;
+
+#[cfg(test)]
+mod auth_repository_tests {
+    use crate::helpers::functions::*;
+    use chrono::{Duration, Utc};
+    use library::adapters::database::ContextManager;
+    use library::domain::auth::entity::{AccountState, TokenStat};
+    use library::utils::ApplicationError;
+    use uuid::Uuid;
+
+    fn session_helper(id: Uuid, token: &str) -> TokenStat {
+        // * Postgres keeps microseconds only.
+        let today = Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        TokenStat {
+            id,
+            refresh_token_hash: TokenStat::hash_refresh_token(token),
+            rotated_refresh_token_hashes: vec![TokenStat::hash_refresh_token("rotated")],
+            expiry_date: today + Duration::days(1),
+            user_agent: Some("repository-test".into()),
+            ip: Some("127.0.0.1".into()),
+            create_dt: today,
+            last_seen_dt: today,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_account() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+
+                let mut account_repo = account_repository_helper(executor.clone());
+                let mut auth_aggregate = account_create_helper();
+
+                let id = account_repo.add(&mut auth_aggregate).await.unwrap();
+                assert_eq!(auth_aggregate.account.id, id);
+
+                executor.write().await.commit().await.unwrap();
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_account() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+
+            let mut account_repo = account_repository_helper(executor.clone());
+            let mut auth_aggregate = account_create_helper();
+            let id: String;
+            let session_id = Uuid::new_v4();
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+                auth_aggregate.sessions = vec![session_helper(session_id, "get")];
+                id = account_repo.add(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                let fetched = account_repo.get(&id).await.unwrap();
+                assert_eq!(fetched.account.email, auth_aggregate.account.email);
+                assert_eq!(fetched.account.nickname, auth_aggregate.account.nickname);
+                assert_eq!(fetched.account.state, AccountState::VerificationRequired);
+                assert_eq!(fetched.account.version, 0);
+                assert_eq!(
+                    fetched.account.hashed_password(),
+                    auth_aggregate.account.hashed_password()
+                );
+                assert_eq!(fetched.sessions, vec![session_helper(session_id, "get")]);
+
+                assert!(account_repo.get("not-existing").await.is_err());
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_update_account() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut account_repo = account_repository_helper(executor.clone());
+            let id: String;
+            let (first_session_id, second_session_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_create_helper();
+                id = account_repo.add(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_transaction_block2: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_repo.get(&id).await.unwrap();
+                assert!(auth_aggregate.sessions.is_empty());
+
+                auth_aggregate.account.state = AccountState::Created;
+                auth_aggregate.account.nickname = "Mago".into();
+                auth_aggregate.sessions = vec![session_helper(first_session_id, "update-first")];
+                account_repo.update(&mut auth_aggregate).await.unwrap();
+                assert_eq!(auth_aggregate.account.version, 1);
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_transaction_block3: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_repo.get(&id).await.unwrap();
+
+                // * Change to sessions alone bumps version too.
+                auth_aggregate
+                    .sessions
+                    .push(session_helper(second_session_id, "update-second"));
+                account_repo.update(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                let auth_aggregate = account_repo.get(&id).await.unwrap();
+                assert_eq!(auth_aggregate.account.state, AccountState::Created);
+                assert_eq!(auth_aggregate.account.nickname, "Mago");
+                assert_eq!(auth_aggregate.account.version, 2);
+                assert_eq!(
+                    auth_aggregate.sessions,
+                    vec![
+                        session_helper(first_session_id, "update-first"),
+                        session_helper(second_session_id, "update-second")
+                    ]
+                );
+            }
+
+            '_transaction_block4: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_repo.get(&id).await.unwrap();
+                auth_aggregate.revoke_session(first_session_id).unwrap();
+                account_repo.update(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+
+                let auth_aggregate = account_repo.get(&id).await.unwrap();
+                assert_eq!(
+                    auth_aggregate.sessions,
+                    vec![session_helper(second_session_id, "update-second")]
+                );
+                assert_eq!(auth_aggregate.account.version, 3);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_update_without_change() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut account_repo = account_repository_helper(executor.clone());
+
+            '_test_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_create_helper();
+                let id = account_repo.add(&mut auth_aggregate).await.unwrap();
+
+                let mut auth_aggregate = account_repo.get(&id).await.unwrap();
+                account_repo.update(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+
+                // * Nothing is written, so version stays.
+                assert_eq!(auth_aggregate.account.version, 0);
+                let auth_aggregate = account_repo.get(&id).await.unwrap();
+                assert_eq!(auth_aggregate.account.version, 0);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_update_account_concurrently() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut account_repo = account_repository_helper(executor.clone());
+            let id: String;
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_create_helper();
+                id = account_repo.add(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                let mut stale = account_repo.get(&id).await.unwrap();
+
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_repo.get(&id).await.unwrap();
+                auth_aggregate.account.nickname = "First".into();
+                account_repo.update(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+
+                executor.write().await.begin().await.unwrap();
+                stale.account.nickname = "Second".into();
+                let Err(ApplicationError::ConcurrencyConflict) =
+                    account_repo.update(&mut stale).await
+                else {
+                    panic!("Stale account must not be written!")
+                };
+                executor.write().await.rollback().await.unwrap();
+
+                let auth_aggregate = account_repo.get(&id).await.unwrap();
+                assert_eq!(auth_aggregate.account.nickname, "First");
+                assert_eq!(auth_aggregate.account.version, 1);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_identity_map() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut account_repo = account_repository_helper(executor.clone());
+            let id: String;
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut auth_aggregate = account_create_helper();
+                id = account_repo.add(&mut auth_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                executor.write().await.begin().await.unwrap();
+                let auth_aggregate = account_repo.get(&id).await.unwrap();
+
+                sqlx::query("UPDATE auth_account SET nickname = 'Changed' WHERE id = $1")
+                    .bind(&id)
+                    .execute(context_manager.read().await.pool)
+                    .await
+                    .unwrap();
+
+                // * Account loaded within the transaction is not queried again.
+                let cached = account_repo.get(&id).await.unwrap();
+                assert_eq!(cached.account.nickname, auth_aggregate.account.nickname);
+                executor.write().await.commit().await.unwrap();
+
+                let fetched = account_repo.get(&id).await.unwrap();
+                assert_eq!(fetched.account.nickname, "Changed");
+            }
+        })
+        .await;
+    }
+}
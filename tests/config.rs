@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod config_tests {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use library::adapters::database::BackpressurePolicy;
+    use library::adapters::mailer::MailTransport;
+    use library::adapters::migration::MigrationMode;
+    use library::adapters::password::PasswordHasherKind;
+    use library::config::Config;
+    use library::utils::ApplicationError;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let config = Config::from_lookup(lookup(&[
+            ("DATABASE_URL", "postgres://localhost/rustweb"),
+            ("DATABASE_MAX_CONNECTIONS", "10"),
+            ("DOMAIN", "0.0.0.0:8080"),
+            (
+                "ALLOW_ORIGINS",
+                "http://localhost:3000, http://localhost:5000",
+            ),
+            ("FEATURE_SWAGGER", "false"),
+            ("DATABASE_MIGRATION_MODE", "apply"),
+            ("DATABASE_STATEMENT_TIMEOUT_MILLIS", "0"),
+            ("EVENT_CHANNEL_CAPACITY", "100"),
+            ("EVENT_CHANNEL_POLICY", "spill"),
+            ("AUTH_JWT_SECRET", "0123456789abcdef0123456789abcdef"),
+            (
+                "AUTH_TOTP_ENCRYPTION_KEY",
+                "fedcba9876543210fedcba9876543210",
+            ),
+        ]))
+        .unwrap();
+
+        assert_eq!(config.database.url, "postgres://localhost/rustweb");
+        assert_eq!(config.database.max_connections, 10);
+        assert_eq!(config.server.domain, "0.0.0.0:8080");
+        assert_eq!(
+            config.server.allow_origins,
+            vec!["http://localhost:3000", "http://localhost:5000"]
+        );
+        assert!(!config.features.swagger);
+        assert_eq!(config.database.migration_mode, MigrationMode::Apply);
+        assert_eq!(config.database.statement_timeout(), None);
+        assert_eq!(config.event_channel.capacity, 100);
+        assert_eq!(config.event_channel.policy, BackpressurePolicy::Spill);
+
+        // * Left to default
+        assert_eq!(config.relay.batch_size, 50);
+        assert_eq!(
+            Config::default().database.statement_timeout(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(config.auth.access_token_ttl_secs, 900);
+        assert_eq!(config.auth.password_hasher, PasswordHasherKind::Argon2id);
+        assert_eq!(config.mail.transport, MailTransport::File);
+        assert!(config.features.scheduler);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_with_unparsable_env() {
+        let Err(ApplicationError::InvalidConfig(problem)) =
+            Config::from_lookup(lookup(&[("DATABASE_MAX_CONNECTIONS", "many")]))
+        else {
+            panic!("Unparsable value must be rejected!")
+        };
+        assert!(problem.contains("DATABASE_MAX_CONNECTIONS"));
+
+        let Err(ApplicationError::InvalidConfig(problem)) =
+            Config::from_lookup(lookup(&[("DATABASE_MIGRATION_MODE", "force")]))
+        else {
+            panic!("Unknown migration mode must be rejected!")
+        };
+        assert!(problem.contains("DATABASE_MIGRATION_MODE"));
+    }
+
+    #[test]
+    fn test_config_overridden_by_toml() {
+        let config = Config::from_lookup(lookup(&[
+            ("DATABASE_URL", "postgres://localhost/rustweb"),
+            ("DATABASE_MAX_CONNECTIONS", "10"),
+        ]))
+        .unwrap()
+        .merge_toml(
+            r#"
+            [database]
+            max_connections = 50
+            replica_urls = ["postgres://replica/rustweb"]
+
+            [relay]
+            interval_millis = 200
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.database.max_connections, 50);
+        assert_eq!(
+            config.database.replica_urls,
+            vec!["postgres://replica/rustweb"]
+        );
+        assert_eq!(config.relay.interval_millis, 200);
+
+        // * What is not in file is kept.
+        assert_eq!(config.database.url, "postgres://localhost/rustweb");
+
+        let Err(ApplicationError::InvalidConfig(_)) = config.merge_toml("[database]\nunknown = 1")
+        else {
+            panic!("Unknown key must be rejected!")
+        };
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let config = Config::from_lookup(lookup(&[
+            ("DATABASE_URL", "mysql://localhost/rustweb"),
+            ("DATABASE_MAX_CONNECTIONS", "5"),
+            ("DATABASE_MIN_CONNECTIONS", "10"),
+            ("DOMAIN", "localhost"),
+            ("ALLOW_ORIGINS", "localhost:3000"),
+            ("EVENT_CHANNEL_CAPACITY", "0"),
+            ("AUTH_JWT_SECRET", "short"),
+            ("AUTH_TOTP_ENCRYPTION_KEY", "short"),
+            ("AUTH_BCRYPT_COST", "3"),
+            ("AUTH_ARGON2_ITERATIONS", "0"),
+            ("MAIL_FROM", "nobody"),
+        ]))
+        .unwrap();
+
+        let Err(ApplicationError::InvalidConfig(problem)) = config.validate() else {
+            panic!("Invalid configuration must be rejected!")
+        };
+
+        // * Every problem is reported at once.
+        assert!(problem.contains("database url"));
+        assert!(problem.contains("database.min_connections"));
+        assert!(problem.contains("server.domain"));
+        assert!(problem.contains("server.allow_origins"));
+        assert!(problem.contains("event_channel.capacity"));
+        assert!(problem.contains("auth.jwt_secret"));
+        assert!(problem.contains("auth.totp_encryption_key"));
+        assert!(problem.contains("auth.bcrypt_cost"));
+        assert!(problem.contains("auth.argon2"));
+        assert!(problem.contains("mail.from"));
+    }
+}
@@ -7,9 +7,13 @@ pub mod database_tests {
 
     use crate::helpers::functions::*;
     use library::{
-        adapters::{database::Executor, repositories::Repository},
-        bootstrap::connection_pool,
+        adapters::{
+            database::{Executor, ReadPreference},
+            repositories::Repository,
+        },
+        bootstrap::{connection_pool, replica_pools},
         domain::board::{entity::BoardState, BoardAggregate},
+        utils::ApplicationError,
     };
     use tokio::sync::RwLock;
 
@@ -75,4 +79,125 @@ pub mod database_tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_serialization_failure_is_retryable() {
+        run_test(async {
+            let pool = connection_pool().await;
+            let executor = Arc::new(RwLock::new(Executor::new(pool)));
+            let id: String;
+
+            '_preparation_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut board_repo = board_repository_helper(executor.clone());
+                let mut board_aggregate = board_create_helper(BoardState::Unpublished);
+                id = board_repo.add(&mut board_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                let update = "UPDATE community_board SET version = version + 1 WHERE id = $1::uuid";
+
+                let mut trx = pool.begin().await.unwrap();
+                sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+                    .execute(&mut trx)
+                    .await
+                    .unwrap();
+                sqlx::query("SELECT version FROM community_board WHERE id = $1::uuid")
+                    .bind(&id)
+                    .execute(&mut trx)
+                    .await
+                    .unwrap();
+
+                // * Concurrent update after the snapshot was taken
+                sqlx::query(update).bind(&id).execute(pool).await.unwrap();
+
+                let err = sqlx::query(update)
+                    .bind(&id)
+                    .execute(&mut trx)
+                    .await
+                    .unwrap_err();
+                trx.rollback().await.unwrap();
+
+                assert!(ApplicationError::DatabaseConnectionError(Box::new(err)).is_retryable());
+                assert!(ApplicationError::ConcurrencyConflict.is_retryable());
+                assert!(!ApplicationError::EntityNotFound.is_retryable());
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_executor_misuse() {
+        run_test(async {
+            let pool = connection_pool().await;
+            let mut executor = Executor::new(pool);
+
+            '_test_block: {
+                let Err(ApplicationError::TransactionError) = executor.commit().await else {
+                    panic!("Commit without transaction must fail!")
+                };
+                let Err(ApplicationError::TransactionError) = executor.rollback().await else {
+                    panic!("Rollback without transaction must fail!")
+                };
+                let Err(ApplicationError::TransactionError) = executor.transaction().await else {
+                    panic!("Transaction must not be given out before begin!")
+                };
+
+                executor.begin().await.unwrap();
+                let Err(ApplicationError::TransactionError) = executor.begin().await else {
+                    panic!("Transaction must not begin twice!")
+                };
+                executor.commit().await.unwrap();
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_read_routing() {
+        run_test(async {
+            let pool = connection_pool().await;
+            let replicas = replica_pools().await;
+            assert!(!replicas.is_empty(), "DATABASE_REPLICA_URLS must be set!");
+
+            '_test_block: {
+                let executor = Executor::new(pool).with_replicas(replicas, ReadPreference::Replica);
+                assert!(!std::ptr::eq(executor.reader(), pool));
+
+                // * Read your writes
+                let executor = Executor::new(pool).with_replicas(replicas, ReadPreference::Primary);
+                assert!(std::ptr::eq(executor.reader(), pool));
+
+                let executor = Executor::new(pool);
+                assert!(std::ptr::eq(executor.reader(), pool));
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_read_within_transaction_goes_to_primary() {
+        run_test(async {
+            let pool = connection_pool().await;
+            let executor = Arc::new(RwLock::new(
+                Executor::new(pool).with_replicas(replica_pools().await, ReadPreference::Replica),
+            ));
+
+            '_test_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut board_repo = board_repository_helper(executor.clone());
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                let id = board_repo.add(&mut board_aggregate).await.unwrap();
+
+                // * Not committed yet, so it can only be read within the transaction.
+                let board_repo = board_repository_helper(executor.clone());
+                assert!(board_repo.get(&id).await.is_ok());
+                executor.write().await.rollback().await.unwrap();
+
+                assert!(board_repo.get(&id).await.is_err());
+            }
+        })
+        .await;
+    }
 }
@@ -1,14 +1,18 @@
 #[cfg(test)]
 pub mod functions {
 
-    use std::sync::Arc;
+    use std::sync::{Arc, Once};
 
     use futures::Future;
     use library::adapters::database::Executor;
     use library::adapters::repositories::{Repository, TRepository};
 
     use dotenv::dotenv;
-    use library::bootstrap::connection_pool;
+    use library::bootstrap::{connection_pool, Boostrap};
+    use library::config::Config;
+    use library::domain::auth::commands::{IssuedToken, Login, RefreshToken, RegisterAccount};
+    use library::domain::auth::entity::Account;
+    use library::domain::auth::AuthAggregate;
     use library::domain::board::entity::{Board, BoardState};
 
     use library::domain::board::BoardAggregate;
@@ -19,16 +23,18 @@ pub mod functions {
 
     pub async fn tear_down() {
         let pool = connection_pool().await;
-        sqlx::query("TRUNCATE community_board, community_comment, auth_account, auth_token_stat,service_outbox")
+        sqlx::query("TRUNCATE community_board, community_comment, auth_account, auth_token_stat, auth_email_verification, auth_password_reset, auth_login_attempt, auth_totp, service_outbox, service_scheduled_command, service_process, service_idempotency")
             .execute(pool)
             .await
             .unwrap();
     }
 
+    #[allow(dead_code)]
     pub fn board_repository_helper(executor: Arc<RwLock<Executor>>) -> Repository<BoardAggregate> {
         Repository::new(executor)
     }
 
+    #[allow(dead_code)]
     pub fn board_create_helper(state: BoardState) -> BoardAggregate {
         let builder = BoardAggregate::builder();
         builder
@@ -42,11 +48,65 @@ pub mod functions {
             .build()
     }
 
+    #[allow(dead_code)]
+    pub fn account_repository_helper(executor: Arc<RwLock<Executor>>) -> Repository<AuthAggregate> {
+        Repository::new(executor)
+    }
+
+    // * Password is hashed with bcrypt as accounts registered before Argon2id was adopted were.
+    #[allow(dead_code)]
+    pub fn account_create_helper() -> AuthAggregate {
+        let builder = AuthAggregate::builder();
+        let id = Uuid::new_v4().to_string();
+        builder
+            .take_account(Account::new(
+                id.clone(),
+                format!("{}@mail.com", id),
+                bcrypt::hash("testpass", 4).unwrap(),
+                format!("Migo-{}", id),
+            ))
+            .build()
+    }
+
+    // * Account registered through the bus has its password "testpass".
+    #[allow(dead_code)]
+    pub fn register_account_cmd(email: &str, nickname: &str) -> RegisterAccount {
+        RegisterAccount {
+            email: email.into(),
+            password: "testpass".into(),
+            nickname: nickname.into(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn login_cmd(email: &str, password: &str) -> Login {
+        Login {
+            email: email.into(),
+            password: password.into(),
+            ip: Some("127.0.0.1".into()),
+            user_agent: Some("test-agent".into()),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn refresh_token_cmd(token: &IssuedToken) -> RefreshToken {
+        RefreshToken {
+            refresh_token: token.refresh_token.clone(),
+        }
+    }
+
+    // * Configuration can be set only once, whereas every test in the binary runs through `run_test`.
+    static CONFIGURE: Once = Once::new();
+
+    #[allow(dead_code)]
     pub async fn run_test<T>(test: T)
     where
         T: Future<Output = ()>,
     {
         dotenv().unwrap();
+        CONFIGURE.call_once(|| {
+            Boostrap::configure(Config::load().unwrap()).expect("Configuration set failed!")
+        });
         Box::pin(test).await;
         tear_down().await;
     }
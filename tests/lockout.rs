@@ -0,0 +1,147 @@
+mod helpers;
+
+#[cfg(test)]
+mod lockout_tests {
+    use std::sync::Arc;
+
+    use crate::helpers::functions::*;
+    use library::adapters::database::ContextManager;
+    use library::adapters::login_attempt::LoginAttempt;
+    use library::adapters::mailer::{InMemoryMailer, Mail};
+    use library::adapters::outbox::Outbox;
+    use library::bootstrap::Boostrap;
+    use library::domain::auth::commands::UnblockAccount;
+    use library::services::handlers::ServiceHandler;
+    use library::utils::ApplicationError;
+
+    // * Account is added directly, and its password is "testpass".
+    async fn add_account() -> (String, String) {
+        let (context_manager, _) = ContextManager::new().await;
+        let executor = context_manager.read().await.executor();
+        let mut account_repo = account_repository_helper(executor.clone());
+
+        let mut auth_aggregate = account_create_helper();
+        executor.write().await.begin().await.unwrap();
+        let id = account_repo.add(&mut auth_aggregate).await.unwrap();
+        executor.write().await.commit().await.unwrap();
+        (id, auth_aggregate.account.email)
+    }
+
+    async fn relay_outbox(mailer: Arc<InMemoryMailer>) -> Vec<Mail> {
+        let (context_manager, _) = ContextManager::new().await;
+        let executor = context_manager.read().await.executor();
+        for outbox in Outbox::get(executor).await.unwrap() {
+            ServiceHandler::handle_outbox(outbox, context_manager.clone(), mailer.clone())
+                .await
+                .unwrap();
+        }
+        mailer.take_sent()
+    }
+
+    #[tokio::test]
+    async fn test_account_locked_after_failures_and_unblocked() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (id, email) = add_account().await;
+
+            '_test_code: {
+                // * Lock is taken once failures reach the limit, which is 5 by default.
+                for _ in 0..5 {
+                    let Err(ApplicationError::InvalidCredentials) =
+                        bus.handle(login_cmd(&email, "wrongpass")).await
+                    else {
+                        panic!("Login must fail on wrong password!")
+                    };
+                }
+                let Err(ApplicationError::AccountLocked) =
+                    bus.handle(login_cmd(&email, "testpass")).await
+                else {
+                    panic!("Locked account must not log in even with right password!")
+                };
+
+                // * Every attempt is recorded.
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                let attempts = LoginAttempt::list(executor.clone(), &id).await.unwrap();
+                assert_eq!(attempts.len(), 6);
+                assert!(attempts.iter().all(|attempt| !attempt.succeeded));
+                assert!(attempts.iter().all(|attempt| {
+                    attempt.ip.as_deref() == Some("127.0.0.1")
+                        && attempt.user_agent.as_deref() == Some("test-agent")
+                }));
+
+                // * Owner is notified of the lock by mail once outbox is relayed.
+                let mailer = Arc::new(InMemoryMailer::default());
+                let mails = relay_outbox(mailer.clone()).await;
+                assert!(mails
+                    .iter()
+                    .any(|mail| mail.to == email && mail.subject.contains("is locked")));
+
+                let Err(ApplicationError::Forbidden) = bus
+                    .handle(UnblockAccount {
+                        account_id: id.clone(),
+                        moderator: false,
+                    })
+                    .await
+                else {
+                    panic!("Only moderator must be allowed to unblock!")
+                };
+                bus.handle(UnblockAccount {
+                    account_id: id.clone(),
+                    moderator: true,
+                })
+                .await
+                .unwrap();
+                let mails = relay_outbox(mailer).await;
+                assert!(mails
+                    .iter()
+                    .any(|mail| mail.to == email && mail.subject.contains("is unblocked")));
+
+                // * Failures before unblock no longer count.
+                let Err(ApplicationError::InvalidCredentials) =
+                    bus.handle(login_cmd(&email, "wrongpass")).await
+                else {
+                    panic!("Login must fail on wrong password!")
+                };
+                bus.handle(login_cmd(&email, "testpass")).await.unwrap();
+
+                let attempts = LoginAttempt::list(executor, &id).await.unwrap();
+                assert!(attempts.first().unwrap().succeeded);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_failures_before_success_are_not_counted() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (_id, email) = add_account().await;
+
+            '_test_code: {
+                for _ in 0..4 {
+                    bus.handle(login_cmd(&email, "wrongpass"))
+                        .await
+                        .unwrap_err();
+                }
+                bus.handle(login_cmd(&email, "testpass")).await.unwrap();
+                for _ in 0..4 {
+                    let Err(ApplicationError::InvalidCredentials) =
+                        bus.handle(login_cmd(&email, "wrongpass")).await
+                    else {
+                        panic!("Account must not be locked!")
+                    };
+                }
+                bus.handle(login_cmd(&email, "testpass")).await.unwrap();
+
+                // * Attempt with unknown email is recorded as well, though not counted toward any account.
+                let Err(ApplicationError::InvalidCredentials) =
+                    bus.handle(login_cmd("unknown@mail.com", "testpass")).await
+                else {
+                    panic!("Login must fail on unknown email!")
+                };
+            }
+        })
+        .await;
+    }
+}
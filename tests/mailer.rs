@@ -0,0 +1,39 @@
+mod helpers;
+
+#[cfg(test)]
+mod mailer_tests {
+    use std::fs;
+
+    use library::adapters::mailer::{FileMailer, Mail, Mailer};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_file_mailer_keeps_mail_within_directory() {
+        let dir = std::env::temp_dir().join(format!("mailer-{}", Uuid::new_v4()));
+        let mailer = FileMailer::new(dir.join("mails"));
+
+        '_test_code: {
+            // * Recipient pointing outside of the directory is not used for the file name.
+            mailer
+                .send(Mail {
+                    from: "noreply@mail.com".into(),
+                    to: "../../escaped".into(),
+                    subject: "Subject".into(),
+                    body: "Body".into(),
+                })
+                .await
+                .unwrap();
+
+            let mails: Vec<_> = fs::read_dir(dir.join("mails"))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .collect();
+            assert_eq!(mails.len(), 1);
+            assert!(fs::read_to_string(&mails[0])
+                .unwrap()
+                .contains("To: ../../escaped"));
+            assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+mod helpers;
+
+#[cfg(test)]
+mod messagebus_tests {
+    use crate::helpers::functions::*;
+    use library::adapters::database::ReadPreference;
+    use library::adapters::outbox::Outbox;
+    use library::bootstrap::{connection_pool, Boostrap};
+    use library::domain::auth::commands::RegisterAccount;
+    use library::domain::board::commands::{CreateBoard, EditBoard};
+    use library::domain::board::entity::BoardState;
+    use library::domain::commands::{Command, ServiceResponse};
+    use library::utils::ApplicationError;
+
+    use uuid::Uuid;
+
+    struct ReadYourWrites;
+    impl Command for ReadYourWrites {
+        const NAME: &'static str = "ReadYourWrites";
+
+        fn read_preference(&self) -> ReadPreference {
+            ReadPreference::Primary
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panic_in_command_handler_is_isolated() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+
+            // * Converting outbox of unknown topic panics inside of `ServiceHandler::handle_outbox`
+            let outbox = Outbox::new(
+                Uuid::new_v4().to_string(),
+                "UnknownEvent".into(),
+                "{}".into(),
+            );
+
+            '_test_case: {
+                match bus.handle(outbox).await {
+                    Err(ApplicationError::HandlerPanicked(handler_name)) => {
+                        assert_eq!(handler_name, "ServiceHandler::handle_outbox")
+                    }
+                    _ => panic!("Panic must be converted into HandlerPanicked!"),
+                };
+            }
+        })
+        .await
+    }
+
+    fn create_board_cmd(title: &str) -> CreateBoard {
+        CreateBoard {
+            author: Uuid::new_v4(),
+            title: title.into(),
+            content: "Content".into(),
+            state: BoardState::Published,
+        }
+    }
+
+    async fn count_boards() -> i64 {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM community_board")
+            .fetch_one(connection_pool().await)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_read_preference_of_command_is_applied() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let pool = connection_pool().await;
+
+            '_test_case: {
+                // * Replica is read from unless the command prefers the primary.
+                let (context_manager, _receiver) = bus.context(&create_board_cmd("Title!")).await;
+                let executor = context_manager.read().await.executor();
+                assert!(!std::ptr::eq(executor.read().await.reader(), pool));
+
+                let (context_manager, _receiver) = bus.context(&ReadYourWrites).await;
+                let executor = context_manager.read().await.executor();
+                assert!(std::ptr::eq(executor.read().await.reader(), pool));
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_response_replayed_for_same_idempotency_key() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let cmd = create_board_cmd("Title!");
+
+            '_test_case: {
+                let first = bus
+                    .handle_idempotent(cmd.clone(), Some("create-board-key".into()))
+                    .await
+                    .unwrap();
+                let second = bus
+                    .handle_idempotent(cmd, Some("create-board-key".into()))
+                    .await
+                    .unwrap();
+
+                assert_eq!(first, second);
+                assert_eq!(count_boards().await, 1);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_same_idempotency_key_with_different_command_conflicts() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+
+            '_test_case: {
+                bus.handle_idempotent(create_board_cmd("Title!"), Some("create-board-key".into()))
+                    .await
+                    .unwrap();
+
+                let Err(ApplicationError::IdempotencyConflict) = bus
+                    .handle_idempotent(
+                        create_board_cmd("Another Title!"),
+                        Some("create-board-key".into()),
+                    )
+                    .await
+                else {
+                    panic!("Different command with the same key must conflict!")
+                };
+                assert_eq!(count_boards().await, 1);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_is_committed_with_response() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+
+            '_test_case: {
+                let res = bus
+                    .handle_idempotent(create_board_cmd("Title!"), Some("stored-key".into()))
+                    .await
+                    .unwrap();
+
+                // * Key is never left claimed without the response to replay.
+                let response = sqlx::query_scalar::<_, Option<String>>(
+                    "SELECT response FROM service_idempotency WHERE idempotency_key = $1",
+                )
+                .bind("stored-key")
+                .fetch_one(connection_pool().await)
+                .await
+                .unwrap();
+                assert_eq!(response, Some(serde_json::to_string(&res).unwrap()));
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_secrets_are_left_out_of_idempotency_fingerprint() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let register_cmd = |password: &str| RegisterAccount {
+                email: "fingerprint@mail.com".into(),
+                password: password.into(),
+                nickname: "Fingerprint".into(),
+            };
+
+            '_test_case: {
+                let first = bus
+                    .handle_idempotent(register_cmd("testpass"), Some("register-key".into()))
+                    .await
+                    .unwrap();
+
+                // * Password is not part of the fingerprint, so the response is replayed instead of conflicting.
+                let second = bus
+                    .handle_idempotent(register_cmd("otherpass"), Some("register-key".into()))
+                    .await
+                    .unwrap();
+                assert_eq!(first, second);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_of_failed_command_is_not_stored() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+
+            '_test_case: {
+                // * Board doesn't exist, so command fails before committing.
+                let edit_cmd = EditBoard {
+                    id: Uuid::new_v4(),
+                    title: Some("Changed".into()),
+                    content: None,
+                    state: None,
+                    editor: Uuid::new_v4(),
+                    moderator: false,
+                };
+                assert!(bus
+                    .handle_idempotent(edit_cmd, Some("reused-key".into()))
+                    .await
+                    .is_err());
+
+                bus.handle_idempotent(create_board_cmd("Title!"), Some("reused-key".into()))
+                    .await
+                    .unwrap();
+                assert_eq!(count_boards().await, 1);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_commands_are_retried() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let create_cmd = create_board_cmd("Title!");
+            let author = create_cmd.author;
+            let Ok(ServiceResponse::String(id)) = bus.handle(create_cmd).await else {
+                panic!("Board creation failed!")
+            };
+
+            '_test_case: {
+                let edit_cmd = |content: &str| EditBoard {
+                    id: id.parse().unwrap(),
+                    title: None,
+                    content: Some(content.into()),
+                    state: None,
+                    editor: author,
+                    moderator: false,
+                };
+                let results = futures::future::join_all([
+                    bus.handle(edit_cmd("First")),
+                    bus.handle(edit_cmd("Second")),
+                    bus.handle(edit_cmd("Third")),
+                ])
+                .await;
+
+                // * Conflicting edits are handled again on fresh version of board.
+                assert!(results.iter().all(|res| res.is_ok()));
+                let version: i32 =
+                    sqlx::query_scalar("SELECT version FROM community_board WHERE id = $1::uuid")
+                        .bind(&id)
+                        .fetch_one(connection_pool().await)
+                        .await
+                        .unwrap();
+                assert_eq!(version, 3);
+            }
+        })
+        .await
+    }
+}
@@ -0,0 +1,69 @@
+mod helpers;
+
+#[cfg(test)]
+mod migration_tests {
+    use crate::helpers::functions::*;
+    use library::{
+        adapters::migration::{Migration, MigrationMode, MigrationState, MIGRATOR},
+        bootstrap::connection_pool,
+        utils::ApplicationError,
+    };
+
+    #[tokio::test]
+    async fn test_migration_status_and_drift() {
+        run_test(async {
+            let pool = connection_pool().await;
+
+            '_test_code: {
+                // * Schema used by tests is expected to be migrated already.
+                let statuses = Migration::status(pool).await.unwrap();
+                let latest = MIGRATOR
+                    .iter()
+                    .filter(|migration| !migration.migration_type.is_down_migration())
+                    .map(|migration| migration.version)
+                    .max();
+                assert!(statuses
+                    .iter()
+                    .all(|status| status.state == MigrationState::Applied));
+                assert_eq!(statuses.last().map(|status| status.version), latest);
+                assert!(Migration::ensure(pool, MigrationMode::Verify).await.is_ok());
+
+                // * Dry run touches nothing.
+                assert!(Migration::up(pool, true).await.unwrap().is_empty());
+                assert_eq!(Migration::down(pool, true).await.unwrap(), latest);
+                assert!(Migration::status(pool)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .all(|status| status.state == MigrationState::Applied));
+
+                // * Migration applied that this build does not know of is drift.
+                sqlx::query(
+                    "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+                    VALUES (1, 'unknown', true, '\\x00', 0)",
+                )
+                .execute(pool)
+                .await
+                .unwrap();
+
+                let statuses = Migration::status(pool).await.unwrap();
+                assert_eq!(statuses.last().unwrap().version, 1);
+                assert_eq!(statuses.last().unwrap().state, MigrationState::Missing);
+                for mode in [MigrationMode::Apply, MigrationMode::Verify] {
+                    let Err(ApplicationError::SchemaDrift(drift)) =
+                        Migration::ensure(pool, mode).await
+                    else {
+                        panic!("Application must refuse to boot on drift!")
+                    };
+                    assert!(drift.contains("Missing"));
+                }
+
+                sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 1")
+                    .execute(pool)
+                    .await
+                    .unwrap();
+            }
+        })
+        .await;
+    }
+}
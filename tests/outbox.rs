@@ -81,15 +81,14 @@ mod test_outbox {
                     .unwrap();
 
                 assert_eq!(vec_of_outbox.len(), 1);
-                let event = vec_of_outbox.get(0).unwrap().convert_event();
+                let event = vec_of_outbox.first().unwrap().convert_event();
                 assert!(event.externally_notifiable());
 
-                let converted = serde_json::from_str(&event.state()).unwrap();
-                match converted {
-                    BoardCreated { .. } => {
+                match serde_json::from_str::<BoardCreated>(&event.state()) {
+                    Ok(BoardCreated { .. }) => {
                         println!("Success!")
                     }
-                    _ => {
+                    Err(_) => {
                         panic!("Failed!")
                     }
                 };
@@ -0,0 +1,260 @@
+mod helpers;
+
+#[cfg(test)]
+mod password_tests {
+    use std::sync::Arc;
+
+    use crate::helpers::functions::*;
+    use library::adapters::database::ContextManager;
+    use library::adapters::mailer::InMemoryMailer;
+    use library::adapters::outbox::Outbox;
+    use library::adapters::password::{
+        hash_password, verify_dummy_password, verify_password, Argon2Hasher, BcryptHasher,
+        PasswordHasher, PasswordVerification,
+    };
+    use library::bootstrap::Boostrap;
+    use library::domain::auth::commands::{
+        ChangePassword, IssuedToken, RequestPasswordReset, ResetPassword,
+    };
+    use library::domain::commands::ServiceResponse;
+    use library::services::handlers::ServiceHandler;
+    use library::utils::ApplicationError;
+
+    // * Parameters kept small for tests to run fast.
+    fn argon2_hasher(memory_kib: u32) -> Argon2Hasher {
+        Argon2Hasher::new(memory_kib, 1, 1).unwrap()
+    }
+
+    #[test]
+    fn test_argon2_hasher() {
+        let hasher = argon2_hasher(1024);
+        let hashed_password = hasher.hash("testpass").unwrap();
+
+        assert!(hashed_password.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert_ne!(hashed_password, hasher.hash("testpass").unwrap());
+        assert!(hasher.verify("testpass", &hashed_password));
+        assert!(!hasher.verify("wrongpass", &hashed_password));
+        assert!(!hasher.verify("testpass", "not a hash"));
+
+        // * Hash made with other cost is to be rehashed.
+        assert!(!hasher.needs_rehash(&hashed_password));
+        assert!(argon2_hasher(2048).needs_rehash(&hashed_password));
+
+        let Err(ApplicationError::InvalidConfig(_)) = Argon2Hasher::new(1024, 0, 1) else {
+            panic!("Invalid argon2 parameters must be rejected!")
+        };
+    }
+
+    #[test]
+    fn test_bcrypt_hash_verified_and_rehashed() {
+        let legacy = BcryptHasher::new(4);
+        let hashed_password = legacy.hash("testpass").unwrap();
+        assert!(!legacy.needs_rehash(&hashed_password));
+        assert!(BcryptHasher::new(5).needs_rehash(&hashed_password));
+
+        // * Hash made by bcrypt is recognized by Argon2id hasher, and is to be rehashed.
+        let hasher = argon2_hasher(1024);
+        assert!(hasher.verify("testpass", &hashed_password));
+        assert!(!hasher.verify("wrongpass", &hashed_password));
+        assert!(hasher.needs_rehash(&hashed_password));
+    }
+
+    #[tokio::test]
+    async fn test_dummy_password_is_never_verified() {
+        let hasher: Arc<dyn PasswordHasher> = Arc::new(argon2_hasher(1024));
+
+        // * Dummy hash is made the way new passwords are, and only once.
+        let dummy_hash = hasher.dummy_hash().unwrap();
+        assert!(dummy_hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(std::ptr::eq(dummy_hash, hasher.dummy_hash().unwrap()));
+
+        for password in ["testpass", "dummy-password"] {
+            assert!(matches!(
+                verify_dummy_password(hasher.clone(), password.into()).await,
+                Ok(PasswordVerification::Invalid)
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_password_off_async_worker() {
+        let hasher: Arc<dyn PasswordHasher> = Arc::new(argon2_hasher(1024));
+        let legacy = bcrypt::hash("testpass", 4).unwrap();
+
+        let Ok(PasswordVerification::Rehashed(rehashed)) =
+            verify_password(hasher.clone(), "testpass".into(), legacy.clone()).await
+        else {
+            panic!("Outdated hash must be rehashed on verification!")
+        };
+        assert!(rehashed.starts_with("$argon2id$"));
+
+        assert!(matches!(
+            verify_password(hasher.clone(), "testpass".into(), rehashed).await,
+            Ok(PasswordVerification::Verified)
+        ));
+        assert!(matches!(
+            verify_password(hasher.clone(), "wrongpass".into(), legacy).await,
+            Ok(PasswordVerification::Invalid)
+        ));
+
+        let hashed_password = hash_password(hasher.clone(), "testpass".into())
+            .await
+            .unwrap();
+        assert!(hasher.verify("testpass", &hashed_password));
+    }
+
+    #[tokio::test]
+    async fn test_password_reset() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            bus.handle(register_account_cmd("reset@mail.com", "Reset"))
+                .await
+                .unwrap();
+            let issued: IssuedToken = bus
+                .handle(login_cmd("reset@mail.com", "testpass"))
+                .await
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            '_test_code: {
+                bus.handle(RequestPasswordReset {
+                    email: "reset@mail.com".into(),
+                })
+                .await
+                .unwrap();
+
+                // * Requested again too soon, which is not told apart either but issues no token.
+                bus.handle(RequestPasswordReset {
+                    email: "reset@mail.com".into(),
+                })
+                .await
+                .unwrap();
+
+                // * Unknown email is not told apart.
+                bus.handle(RequestPasswordReset {
+                    email: "unknown@mail.com".into(),
+                })
+                .await
+                .unwrap();
+
+                // * Reset token is delivered by mail once outbox is relayed.
+                let mailer = Arc::new(InMemoryMailer::default());
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                for outbox in Outbox::get(executor).await.unwrap() {
+                    ServiceHandler::handle_outbox(outbox, context_manager.clone(), mailer.clone())
+                        .await
+                        .unwrap();
+                }
+                let mails = mailer.take_sent();
+                let Some(mail) = mails
+                    .iter()
+                    .find(|mail| mail.to == "reset@mail.com" && mail.subject.contains("password"))
+                else {
+                    panic!("Reset mail must be sent!")
+                };
+                assert!(mails.iter().all(|mail| mail.to != "unknown@mail.com"));
+                assert_eq!(
+                    mails
+                        .iter()
+                        .filter(
+                            |mail| mail.to == "reset@mail.com" && mail.subject.contains("password")
+                        )
+                        .count(),
+                    1
+                );
+                let token = mail.body.lines().rfind(|line| !line.is_empty()).unwrap();
+
+                bus.handle(ResetPassword {
+                    token: token.into(),
+                    new_password: "newpass".into(),
+                })
+                .await
+                .unwrap();
+
+                // * Token is single-use.
+                let Err(ApplicationError::InvalidToken) = bus
+                    .handle(ResetPassword {
+                        token: token.into(),
+                        new_password: "anotherpass".into(),
+                    })
+                    .await
+                else {
+                    panic!("Used token must be rejected!")
+                };
+
+                let Err(ApplicationError::InvalidCredentials) =
+                    bus.handle(login_cmd("reset@mail.com", "testpass")).await
+                else {
+                    panic!("Old password must be rejected!")
+                };
+                // * Refresh token issued before reset is revoked.
+                let Err(ApplicationError::InvalidToken) =
+                    bus.handle(refresh_token_cmd(&issued)).await
+                else {
+                    panic!("Refresh token must be revoked on reset!")
+                };
+                bus.handle(login_cmd("reset@mail.com", "newpass"))
+                    .await
+                    .unwrap();
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_change_password() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(register_account_cmd("change@mail.com", "Change"))
+                .await
+            else {
+                panic!("Account must be registered!")
+            };
+            let issued: IssuedToken = bus
+                .handle(login_cmd("change@mail.com", "testpass"))
+                .await
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            '_test_code: {
+                let Err(ApplicationError::InvalidCredentials) = bus
+                    .handle(ChangePassword {
+                        account_id: id.clone(),
+                        current_password: "wrongpass".into(),
+                        new_password: "newpass".into(),
+                    })
+                    .await
+                else {
+                    panic!("Wrong current password must be rejected!")
+                };
+
+                bus.handle(ChangePassword {
+                    account_id: id.clone(),
+                    current_password: "testpass".into(),
+                    new_password: "newpass".into(),
+                })
+                .await
+                .unwrap();
+
+                let Err(ApplicationError::InvalidToken) =
+                    bus.handle(refresh_token_cmd(&issued)).await
+                else {
+                    panic!("Refresh token must be revoked on change!")
+                };
+                let Err(ApplicationError::InvalidCredentials) =
+                    bus.handle(login_cmd("change@mail.com", "testpass")).await
+                else {
+                    panic!("Old password must be rejected!")
+                };
+                bus.handle(login_cmd("change@mail.com", "newpass"))
+                    .await
+                    .unwrap();
+            }
+        })
+        .await;
+    }
+}
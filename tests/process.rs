@@ -0,0 +1,142 @@
+mod helpers;
+
+#[cfg(test)]
+mod process_tests {
+    use crate::helpers::functions::*;
+
+    use chrono::Duration;
+    use library::adapters::database::ContextManager;
+    use library::adapters::process::{Process, ProcessStatus};
+    use library::adapters::repositories::{Repository, TRepository};
+    use library::bootstrap::{connection_pool, Boostrap};
+    use library::domain::board::commands::CreateBoard;
+    use library::domain::board::entity::BoardState;
+    use library::domain::board::process::{BoardModeration, ModerationStep};
+    use library::domain::board::BoardAggregate;
+    use library::domain::commands::ServiceResponse;
+    use library::services::scheduler::Scheduler;
+
+    use uuid::Uuid;
+
+    async fn count_scheduled_commands() -> i64 {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM service_scheduled_command")
+            .fetch_one(connection_pool().await)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_board_moderation_approved() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(CreateBoard {
+                    author: Uuid::new_v4(),
+                    title: "Why Rust?".into(),
+                    content: "Ownership is fun".into(),
+                    state: BoardState::Published,
+                })
+                .await
+            else {
+                panic!("Board creation failed!")
+            };
+
+            '_test_case: {
+                // * Moderation check is issued as follow-up command, pending until then.
+                let process = Process::<BoardModeration>::get(executor.clone(), &id)
+                    .await
+                    .unwrap();
+                assert_eq!(process.status, ProcessStatus::Running);
+                assert_eq!(process.state.step, ModerationStep::Requested);
+                assert!(process.deadline.is_some());
+                assert_eq!(count_scheduled_commands().await, 1);
+
+                // * Process moves on with the result of the check.
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 1);
+                let process = Process::<BoardModeration>::get(executor, &id)
+                    .await
+                    .unwrap();
+                assert_eq!(process.status, ProcessStatus::Completed);
+                assert_eq!(process.state.step, ModerationStep::Approved);
+                assert_eq!(count_scheduled_commands().await, 1);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_board_moderation_rejected_is_compensated() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(CreateBoard {
+                    author: Uuid::new_v4(),
+                    title: "Cheap deals".into(),
+                    content: "This is SPAM".into(),
+                    state: BoardState::Published,
+                })
+                .await
+            else {
+                panic!("Board creation failed!")
+            };
+
+            '_test_case: {
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 1);
+                let process = Process::<BoardModeration>::get(executor.clone(), &id)
+                    .await
+                    .unwrap();
+                assert_eq!(process.status, ProcessStatus::Compensated);
+                assert_eq!(process.state.step, ModerationStep::Rejected);
+
+                // * Compensation is dispatched as follow-up command
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 1);
+                let board_aggregate = Repository::<BoardAggregate>::new(executor)
+                    .get(&id)
+                    .await
+                    .unwrap();
+                assert_eq!(board_aggregate.board.state, BoardState::Deleted);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_expired_process_is_timed_out() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let board_id = Uuid::new_v4();
+
+            '_preparation_block: {
+                let mut process = Process::start(
+                    board_id.to_string(),
+                    BoardModeration::new(board_id),
+                    Some(Duration::seconds(-1)),
+                );
+                executor.write().await.begin().await.unwrap();
+                process.save(executor.clone()).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_case: {
+                assert_eq!(Scheduler::time_out_processes().await.unwrap(), 1);
+
+                let process = Process::<BoardModeration>::get(executor, &board_id.to_string())
+                    .await
+                    .unwrap();
+                assert_eq!(process.status, ProcessStatus::TimedOut);
+                assert_eq!(count_scheduled_commands().await, 1);
+
+                // * Process that has timed out is not timed out again
+                assert_eq!(Scheduler::time_out_processes().await.unwrap(), 0);
+            }
+        })
+        .await
+    }
+}
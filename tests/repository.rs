@@ -12,6 +12,7 @@ mod repository_tests {
 
     use library::domain::board::BoardAggregate;
     use library::domain::builder::{Buildable, Builder};
+    use library::utils::ApplicationError;
 
     use uuid::Uuid;
 
@@ -63,6 +64,23 @@ mod repository_tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_get_board_with_malformed_id() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let board_repo = board_repository_helper(executor);
+
+            '_test_block: {
+                let Err(ApplicationError::EntityNotFound) = board_repo.get("not-a-uuid").await
+                else {
+                    panic!("Malformed id must be reported as not found!")
+                };
+            }
+        })
+        .await;
+    }
+
     #[tokio::test]
     async fn test_get_board_with_different_state() {
         run_test(async {
@@ -109,12 +127,16 @@ mod repository_tests {
             '_transaction_block2: {
                 executor.write().await.begin().await.unwrap();
                 let mut board_aggregate = board_repo.get(&id).await.unwrap();
-                board_aggregate.update_board(EditBoard {
-                    id: Uuid::from_str(&id).unwrap(),
-                    title: None,
-                    content: None,
-                    state: Some(BoardState::Deleted),
-                });
+                board_aggregate
+                    .update_board(EditBoard {
+                        id: Uuid::from_str(&id).unwrap(),
+                        title: None,
+                        content: None,
+                        state: Some(BoardState::Deleted),
+                        editor: board_aggregate.board.author,
+                        moderator: false,
+                    })
+                    .unwrap();
 
                 board_repo.update(&mut board_aggregate).await.unwrap();
                 executor.write().await.commit().await.unwrap();
@@ -163,6 +185,7 @@ mod repository_tests {
                     .update(&mut initial_board_aggregate)
                     .await
                     .unwrap();
+                assert_eq!(initial_board_aggregate.board.version, 1);
 
                 executor.write().await.commit().await.unwrap();
 
@@ -221,4 +244,130 @@ mod repository_tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_update_tracks_every_comment_change() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut board_repo = board_repository_helper(executor.clone());
+            let id: String;
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                id = board_repo.add(&mut board_aggregate).await.unwrap();
+
+                let board_id = board_aggregate.board.id;
+                board_aggregate.comments = ["First", "Second", "Third"]
+                    .into_iter()
+                    .map(|content| Comment::new(board_id, Uuid::new_v4(), content))
+                    .collect();
+                board_repo.update(&mut board_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_transaction_block2: {
+                executor.write().await.begin().await.unwrap();
+                let mut board_aggregate = board_repo.get(&id).await.unwrap();
+                let board_id = board_aggregate.board.id;
+
+                board_aggregate
+                    .comments
+                    .sort_by(|a, b| a.content.cmp(&b.content));
+                // * First, Second, Third
+                board_aggregate.comments[0].content = "First Edited".into();
+                board_aggregate.comments[1].content = "Second Edited".into();
+                board_aggregate.comments.remove(2);
+                board_aggregate
+                    .comments
+                    .push(Comment::new(board_id, Uuid::new_v4(), "Fourth"));
+                board_aggregate
+                    .comments
+                    .push(Comment::new(board_id, Uuid::new_v4(), "Fifth"));
+
+                board_repo.update(&mut board_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                let board_aggregate = board_repo.get(&id).await.unwrap();
+                let mut contents: Vec<String> = board_aggregate
+                    .comments
+                    .into_iter()
+                    .map(|c| c.content)
+                    .collect();
+                contents.sort();
+
+                assert_eq!(
+                    contents,
+                    vec!["Fifth", "First Edited", "Fourth", "Second Edited"]
+                );
+                assert_eq!(board_aggregate.board.version, 2);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_update_without_change() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut board_repo = board_repository_helper(executor.clone());
+
+            '_test_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                let id = board_repo.add(&mut board_aggregate).await.unwrap();
+
+                let mut board_aggregate = board_repo.get(&id).await.unwrap();
+                board_repo.update(&mut board_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+
+                // * Nothing is written, so version stays.
+                assert_eq!(board_aggregate.board.version, 0);
+                let board_aggregate = board_repo.get(&id).await.unwrap();
+                assert_eq!(board_aggregate.board.version, 0);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_identity_map() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let mut board_repo = board_repository_helper(executor.clone());
+            let id: String;
+
+            '_transaction_block: {
+                executor.write().await.begin().await.unwrap();
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                id = board_repo.add(&mut board_aggregate).await.unwrap();
+                executor.write().await.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                executor.write().await.begin().await.unwrap();
+                let board_aggregate = board_repo.get(&id).await.unwrap();
+
+                sqlx::query("UPDATE community_board SET title = 'Changed' WHERE id = $1::uuid")
+                    .bind(&id)
+                    .execute(context_manager.read().await.pool)
+                    .await
+                    .unwrap();
+
+                // * Board loaded within the transaction is not queried again.
+                let cached = board_repo.get(&id).await.unwrap();
+                assert_eq!(cached.board.title, board_aggregate.board.title);
+                executor.write().await.commit().await.unwrap();
+
+                let fetched = board_repo.get(&id).await.unwrap();
+                assert_eq!(fetched.board.title, "Changed");
+            }
+        })
+        .await;
+    }
 }
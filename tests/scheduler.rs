@@ -0,0 +1,178 @@
+mod helpers;
+
+#[cfg(test)]
+mod scheduler_tests {
+    use crate::helpers::functions::*;
+
+    use chrono::{Duration, Utc};
+    use library::adapters::database::ContextManager;
+    use library::adapters::scheduled_command::{ScheduledCommand, ScheduledCommandState};
+    use library::bootstrap::{connection_pool, Boostrap};
+    use library::domain::board::commands::CreateBoard;
+    use library::domain::board::entity::BoardState;
+    use library::services::scheduler::Scheduler;
+    use library::utils::ApplicationError;
+
+    use uuid::Uuid;
+
+    fn create_board_cmd() -> CreateBoard {
+        CreateBoard {
+            author: Uuid::new_v4(),
+            title: "Scheduled Title".into(),
+            content: "Scheduled Content".into(),
+            state: BoardState::Published,
+        }
+    }
+
+    async fn count_boards() -> i64 {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM community_board")
+            .fetch_one(connection_pool().await)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_due_command_is_dispatched_once() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+
+            let id = ScheduledCommand::new(&create_board_cmd(), Utc::now() - Duration::seconds(1))
+                .unwrap()
+                .add(executor.clone())
+                .await
+                .unwrap();
+
+            '_test_case: {
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 1);
+                assert_eq!(count_boards().await, 1);
+
+                let scheduled = ScheduledCommand::get(executor.clone(), id).await.unwrap();
+                assert_eq!(scheduled.state(), &ScheduledCommandState::Processed);
+                assert_eq!(scheduled.command_type(), "CreateBoard");
+
+                // * Processed command must not be dispatched again,
+                // * though moderation check issued for the board created is.
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 1);
+                assert_eq!(count_boards().await, 1);
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 0);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_command_handled_before_crash_is_not_handled_again() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+            let cmd = create_board_cmd();
+
+            let id = ScheduledCommand::new(&cmd, Utc::now() - Duration::seconds(1))
+                .unwrap()
+                .add(executor.clone())
+                .await
+                .unwrap();
+            let scheduled = ScheduledCommand::get(executor.clone(), id).await.unwrap();
+
+            '_test_case: {
+                // * Handled as dispatched, but the worker crashed before tagging it processed.
+                bus.handle_idempotent(cmd, Some(scheduled.idempotency_key()))
+                    .await
+                    .unwrap();
+                assert_eq!(count_boards().await, 1);
+
+                // * Along with moderation check issued for the board created.
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 2);
+                assert_eq!(count_boards().await, 1);
+                let scheduled = ScheduledCommand::get(executor.clone(), id).await.unwrap();
+                assert_eq!(scheduled.state(), &ScheduledCommandState::Processed);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_command_not_yet_due_is_not_dispatched() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+
+            ScheduledCommand::new(&create_board_cmd(), Utc::now() + Duration::days(7))
+                .unwrap()
+                .add(executor.clone())
+                .await
+                .unwrap();
+
+            '_test_case: {
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 0);
+                assert_eq!(count_boards().await, 0);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_cancel_scheduled_command() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+
+            let id = ScheduledCommand::new(&create_board_cmd(), Utc::now() - Duration::seconds(1))
+                .unwrap()
+                .add(executor.clone())
+                .await
+                .unwrap();
+
+            '_test_case: {
+                ScheduledCommand::cancel(executor.clone(), id)
+                    .await
+                    .unwrap();
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 0);
+                assert_eq!(count_boards().await, 0);
+
+                // * Canceling twice is not allowed
+                let Err(ApplicationError::EntityNotFound) =
+                    ScheduledCommand::cancel(executor.clone(), id).await
+                else {
+                    panic!("Canceled command must not be found!")
+                };
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_schedule_with_same_dedupe_key() {
+        run_test(async {
+            let (context_manager, _) = ContextManager::new().await;
+            let executor = context_manager.read().await.executor();
+            let bus = Boostrap::message_bus().await;
+            let due_dt = Utc::now() - Duration::seconds(1);
+
+            let first = ScheduledCommand::new(&create_board_cmd(), due_dt)
+                .unwrap()
+                .with_dedupe_key("publish-board")
+                .add(executor.clone())
+                .await
+                .unwrap();
+            let second = ScheduledCommand::new(&create_board_cmd(), due_dt)
+                .unwrap()
+                .with_dedupe_key("publish-board")
+                .add(executor.clone())
+                .await
+                .unwrap();
+
+            '_test_case: {
+                assert_eq!(first, second);
+                assert_eq!(Scheduler::dispatch_due_commands(&bus).await.unwrap(), 1);
+                assert_eq!(count_boards().await, 1);
+            }
+        })
+        .await
+    }
+}
@@ -9,12 +9,15 @@ pub mod service_tests {
     use library::adapters::database::ContextManager;
     use library::adapters::repositories::Repository;
 
-    use library::domain::board::commands::{AddComment, CreateBoard, EditBoard};
+    use library::domain::board::commands::{AddComment, CreateBoard, EditBoard, EditComment};
     use library::domain::board::entity::BoardState;
     use library::domain::board::BoardAggregate;
+    use library::domain::commands::ServiceResponse;
 
+    use library::bootstrap::Boostrap;
     use library::services::handlers::ServiceHandler;
     use library::services::unit_of_work::UnitOfWork;
+    use library::utils::ApplicationError;
 
     use uuid::Uuid;
 
@@ -54,6 +57,7 @@ pub mod service_tests {
             let (context_manager, _recv) = ContextManager::new().await;
 
             let id: String;
+            let author: Uuid;
 
             '_preparation_block: {
                 let mut uow =
@@ -63,6 +67,7 @@ pub mod service_tests {
 
                 let mut board_aggregate = board_create_helper(BoardState::Published);
                 id = uow.repository().add(&mut board_aggregate).await.unwrap();
+                author = board_aggregate.board.author;
                 assert_eq!(board_aggregate.board.id.to_string(), id);
                 uow.commit().await.unwrap();
             }
@@ -73,6 +78,8 @@ pub mod service_tests {
                     title: None,
                     content: Some("Changed to this".to_string()),
                     state: None,
+                    editor: author,
+                    moderator: false,
                 };
 
                 let id = cmd.id.clone().to_string();
@@ -137,4 +144,195 @@ pub mod service_tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_edit_board_on_stale_version_conflicts() {
+        run_test(async {
+            let (context_manager, _recv) = ContextManager::new().await;
+            let id: String;
+            let author: Uuid;
+
+            '_preparation_block: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                id = uow.repository().add(&mut board_aggregate).await.unwrap();
+                author = board_aggregate.board.author;
+                uow.commit().await.unwrap();
+            }
+
+            '_test_block: {
+                let edit_cmd = |content: &str| EditBoard {
+                    id: Uuid::from_str(&id).unwrap(),
+                    title: None,
+                    content: Some(content.into()),
+                    state: None,
+                    editor: author,
+                    moderator: false,
+                };
+
+                // * Both load the same version of board before either of them writes.
+                // * Each is begun on its own context, as one begun on the same context would join the other.
+                let (other_context_manager, _other_recv) = ContextManager::new().await;
+                let mut first_uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut second_uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(other_context_manager)
+                        .await
+                        .unwrap();
+                let mut first = first_uow.repository().get(&id).await.unwrap();
+                let mut second = second_uow.repository().get(&id).await.unwrap();
+
+                first.update_board(edit_cmd("First")).unwrap();
+                first_uow.repository().update(&mut first).await.unwrap();
+                first_uow.commit().await.unwrap();
+
+                second.update_board(edit_cmd("Second")).unwrap();
+                let Err(ApplicationError::ConcurrencyConflict) =
+                    second_uow.repository().update(&mut second).await
+                else {
+                    panic!("Update on stale version must conflict!")
+                };
+                second_uow.rollback().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let board_aggregate = uow.repository().get(&id).await.unwrap();
+                assert_eq!(board_aggregate.board.content, "First");
+                assert_eq!(board_aggregate.board.version, 1);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_racing_edit_board_commands() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let author = Uuid::new_v4();
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(CreateBoard {
+                    author,
+                    title: "Title!".to_string(),
+                    content: "Content".to_string(),
+                    state: BoardState::Published,
+                })
+                .await
+            else {
+                panic!("Board creation failed!")
+            };
+
+            '_test_block: {
+                let edit_cmd = |content: &str| EditBoard {
+                    id: Uuid::from_str(&id).unwrap(),
+                    title: None,
+                    content: Some(content.into()),
+                    state: None,
+                    editor: author,
+                    moderator: false,
+                };
+                let (first, second) = tokio::join!(
+                    bus.handle(edit_cmd("First")),
+                    bus.handle(edit_cmd("Second"))
+                );
+
+                // * Whichever loses the race must be told so rather than silently succeeding.
+                let mut succeeded = 0;
+                for res in [first, second] {
+                    match res {
+                        Ok(_) => succeeded += 1,
+                        Err(ApplicationError::ConcurrencyConflict) => (),
+                        Err(err) => panic!("Unexpected Error! {}", err),
+                    }
+                }
+                assert!(succeeded >= 1);
+
+                let (context_manager, _recv) = ContextManager::new().await;
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let board_aggregate = uow.repository().get(&id).await.unwrap();
+                assert_eq!(board_aggregate.board.version, succeeded);
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_edit_by_other_than_author_is_forbidden() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (author, commenter, stranger) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(CreateBoard {
+                    author,
+                    title: "Title!".to_string(),
+                    content: "Content".to_string(),
+                    state: BoardState::Published,
+                })
+                .await
+            else {
+                panic!("Board creation failed!")
+            };
+            let board_id = Uuid::from_str(&id).unwrap();
+            bus.handle(AddComment {
+                board_id,
+                author: commenter,
+                content: "Comment".into(),
+            })
+            .await
+            .unwrap();
+
+            let (context_manager, _recv) = ContextManager::new().await;
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                .await
+                .unwrap();
+            let comment_id = uow.repository().get(&id).await.unwrap().comments[0].id;
+            uow.rollback().await.unwrap();
+
+            '_test_block: {
+                let edit_board_cmd = |editor: Uuid, moderator: bool| EditBoard {
+                    id: board_id,
+                    title: Some("Changed".into()),
+                    content: None,
+                    state: None,
+                    editor,
+                    moderator,
+                };
+                let Err(ApplicationError::Forbidden) =
+                    bus.handle(edit_board_cmd(stranger, false)).await
+                else {
+                    panic!("Board must be edited only by its author!")
+                };
+                bus.handle(edit_board_cmd(author, false)).await.unwrap();
+                bus.handle(edit_board_cmd(stranger, true)).await.unwrap();
+
+                // * Author of board is not author of comment.
+                let edit_comment_cmd = |editor: Uuid, moderator: bool| EditComment {
+                    board_id,
+                    id: comment_id,
+                    content: "Changed".into(),
+                    editor,
+                    moderator,
+                };
+                let Err(ApplicationError::Forbidden) =
+                    bus.handle(edit_comment_cmd(author, false)).await
+                else {
+                    panic!("Comment must be edited only by its author!")
+                };
+                bus.handle(edit_comment_cmd(commenter, false))
+                    .await
+                    .unwrap();
+                bus.handle(edit_comment_cmd(stranger, true)).await.unwrap();
+            }
+        })
+        .await;
+    }
 }
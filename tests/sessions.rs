@@ -0,0 +1,168 @@
+mod helpers;
+
+#[cfg(test)]
+mod sessions_tests {
+    use crate::helpers::functions::*;
+    use library::adapters::jwt::AccessToken;
+    use library::bootstrap::Boostrap;
+    use library::domain::auth::commands::{
+        IssuedToken, ListSessions, Login, RevokeAllSessions, RevokeSession, SessionSummary,
+    };
+    use library::domain::commands::ServiceResponse;
+    use library::services::messagebus::MessageBus;
+    use library::utils::ApplicationError;
+    use uuid::Uuid;
+
+    async fn register_account(bus: &MessageBus, email: &str, nickname: &str) -> String {
+        let Ok(ServiceResponse::String(id)) =
+            bus.handle(register_account_cmd(email, nickname)).await
+        else {
+            panic!("Account must be registered!")
+        };
+        id
+    }
+
+    async fn login(bus: &MessageBus, email: &str, user_agent: &str) -> IssuedToken {
+        bus.handle(Login {
+            user_agent: Some(user_agent.into()),
+            ..login_cmd(email, "testpass")
+        })
+        .await
+        .unwrap()
+        .try_into()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_and_revoke_sessions() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let id = register_account(&bus, "sessions@mail.com", "Sessions").await;
+            let laptop = login(&bus, "sessions@mail.com", "laptop").await;
+            let phone = login(&bus, "sessions@mail.com", "phone").await;
+
+            '_test_code: {
+                let laptop_session_id = AccessToken::verify(&laptop.access_token).unwrap().sid;
+                let phone_session_id = AccessToken::verify(&phone.access_token).unwrap().sid;
+                assert_ne!(laptop_session_id, phone_session_id);
+
+                // * Each device is listed, oldest first, with the one the request is made with marked.
+                let sessions: Vec<SessionSummary> = bus
+                    .handle(ListSessions {
+                        account_id: id.clone(),
+                        current_session_id: Some(laptop_session_id),
+                    })
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert_eq!(sessions.len(), 2);
+                assert_eq!(sessions[0].id, laptop_session_id);
+                assert_eq!(sessions[0].user_agent.as_deref(), Some("laptop"));
+                assert_eq!(sessions[0].ip.as_deref(), Some("127.0.0.1"));
+                assert!(sessions[0].current);
+                assert_eq!(sessions[1].user_agent.as_deref(), Some("phone"));
+                assert!(!sessions[1].current);
+
+                let Err(ApplicationError::EntityNotFound) = bus
+                    .handle(RevokeSession {
+                        account_id: id.clone(),
+                        session_id: Uuid::new_v4(),
+                    })
+                    .await
+                else {
+                    panic!("Unknown session must not be revoked!")
+                };
+                bus.handle(RevokeSession {
+                    account_id: id.clone(),
+                    session_id: phone_session_id,
+                })
+                .await
+                .unwrap();
+
+                // * Neither refresh token nor access token of the session revoked is accepted.
+                let Err(ApplicationError::InvalidToken) =
+                    bus.handle(refresh_token_cmd(&phone)).await
+                else {
+                    panic!("Refresh token of revoked session must be rejected!")
+                };
+                let Err(ApplicationError::InvalidToken) =
+                    AccessToken::verify_active(&phone.access_token).await
+                else {
+                    panic!("Access token of revoked session must be rejected!")
+                };
+
+                // * The other session is left as it is.
+                AccessToken::verify_active(&laptop.access_token)
+                    .await
+                    .unwrap();
+                let refreshed: IssuedToken = bus
+                    .handle(refresh_token_cmd(&laptop))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert_eq!(
+                    AccessToken::verify(&refreshed.access_token).unwrap().sid,
+                    laptop_session_id
+                );
+
+                // * Logging out everywhere ends every session.
+                login(&bus, "sessions@mail.com", "tablet").await;
+                bus.handle(RevokeAllSessions {
+                    account_id: id.clone(),
+                })
+                .await
+                .unwrap();
+                let Err(ApplicationError::InvalidToken) =
+                    AccessToken::verify_active(&refreshed.access_token).await
+                else {
+                    panic!("Access token must be rejected after logging out everywhere!")
+                };
+                let sessions: Vec<SessionSummary> = bus
+                    .handle(ListSessions {
+                        account_id: id,
+                        current_session_id: None,
+                    })
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert!(sessions.is_empty());
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_token_reuse_revokes_only_its_session() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            register_account(&bus, "reuse-session@mail.com", "ReuseSession").await;
+            let stolen = login(&bus, "reuse-session@mail.com", "laptop").await;
+            let other = login(&bus, "reuse-session@mail.com", "phone").await;
+
+            '_test_code: {
+                let rotated: IssuedToken = bus
+                    .handle(refresh_token_cmd(&stolen))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                let Err(ApplicationError::TokenReused) =
+                    bus.handle(refresh_token_cmd(&stolen)).await
+                else {
+                    panic!("Reuse must be detected!")
+                };
+                let Err(ApplicationError::InvalidToken) =
+                    bus.handle(refresh_token_cmd(&rotated)).await
+                else {
+                    panic!("Session the token is reused on must be revoked!")
+                };
+
+                bus.handle(refresh_token_cmd(&other)).await.unwrap();
+            }
+        })
+        .await;
+    }
+}
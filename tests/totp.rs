@@ -0,0 +1,321 @@
+mod helpers;
+
+#[cfg(test)]
+mod totp_tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::helpers::functions::*;
+    use library::adapters::database::ContextManager;
+    use library::adapters::totp::{SecretCipher, Totp, TotpAlgorithm};
+    use library::bootstrap::Boostrap;
+    use library::domain::auth::commands::{
+        ConfirmTotp, DisableTotp, EnableTotp, IssuedToken, MfaChallenge, TotpEnrollment, VerifyMfa,
+    };
+    use library::domain::commands::ServiceResponse;
+    use library::services::messagebus::MessageBus;
+    use library::utils::ApplicationError;
+
+    // * Test vectors of RFC 6238, Appendix B, which are 8 digits with seed of the length of each hash.
+    #[test]
+    fn test_rfc6238_vectors() {
+        let sha1 = Totp::with_params(b"12345678901234567890".to_vec(), TotpAlgorithm::Sha1, 8, 30);
+        let sha256 = Totp::with_params(
+            b"12345678901234567890123456789012".to_vec(),
+            TotpAlgorithm::Sha256,
+            8,
+            30,
+        );
+        let sha512 = Totp::with_params(
+            b"1234567890123456789012345678901234567890123456789012345678901234".to_vec(),
+            TotpAlgorithm::Sha512,
+            8,
+            30,
+        );
+
+        for (time, sha1_code, sha256_code, sha512_code) in [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ] {
+            let time = Utc.timestamp_opt(time, 0).unwrap();
+            assert_eq!(sha1.generate(time), sha1_code);
+            assert_eq!(sha256.generate(time), sha256_code);
+            assert_eq!(sha512.generate(time), sha512_code);
+        }
+    }
+
+    #[test]
+    fn test_totp_verify_within_drift() {
+        let totp = Totp::new(Totp::generate_secret());
+        let now = Utc::now();
+        let code = totp.generate(now);
+        assert_eq!(code.len(), 6);
+
+        assert_eq!(totp.verify(&code, now), Some(totp.step(now)));
+        assert_eq!(
+            totp.verify(&code, now + Duration::seconds(30)),
+            Some(totp.step(now))
+        );
+        assert_eq!(totp.verify(&code, now + Duration::seconds(90)), None);
+
+        let uri = totp.provisioning_uri("rustiful", "migo@mail.com");
+        assert!(uri.starts_with("otpauth://totp/rustiful:migo%40mail%2Ecom?"));
+        assert!(uri.contains(&format!("secret={}", totp.encoded_secret())));
+    }
+
+    #[test]
+    fn test_secret_cipher() {
+        let cipher = SecretCipher::new("0123456789abcdef0123456789abcdef");
+        let encrypted = cipher.encrypt(b"secret").unwrap();
+
+        // * Nonce is random, so the same secret is encrypted differently every time.
+        assert_ne!(encrypted, cipher.encrypt(b"secret").unwrap());
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"secret");
+
+        let Err(ApplicationError::SecretEncryptionError(_)) =
+            SecretCipher::new("fedcba9876543210fedcba9876543210").decrypt(&encrypted)
+        else {
+            panic!("Secret must not be decrypted with other key!")
+        };
+        let mut tampered = encrypted.into_bytes();
+        tampered[20] = if tampered[20] == b'A' { b'B' } else { b'A' };
+        let Err(ApplicationError::SecretEncryptionError(_)) =
+            cipher.decrypt(&String::from_utf8(tampered).unwrap())
+        else {
+            panic!("Tampered secret must be rejected!")
+        };
+    }
+
+    fn verify_mfa_cmd(challenge: &MfaChallenge, code: &str) -> VerifyMfa {
+        VerifyMfa {
+            mfa_token: challenge.mfa_token.clone(),
+            code: code.into(),
+            ip: None,
+            user_agent: None,
+        }
+    }
+
+    // * Account registered with TOTP confirmed, returned with its TOTP and recovery codes.
+    async fn register_with_totp(
+        bus: &MessageBus,
+        email: &str,
+        nickname: &str,
+    ) -> (String, Totp, Vec<String>) {
+        let Ok(ServiceResponse::String(id)) =
+            bus.handle(register_account_cmd(email, nickname)).await
+        else {
+            panic!("Account must be registered!")
+        };
+        let enrollment: TotpEnrollment = bus
+            .handle(EnableTotp {
+                account_id: id.clone(),
+            })
+            .await
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let totp = Totp::from_encoded_secret(&enrollment.secret).unwrap();
+        bus.handle(ConfirmTotp {
+            account_id: id.clone(),
+            code: totp.generate(Utc::now()),
+        })
+        .await
+        .unwrap();
+        (id, totp, enrollment.recovery_codes)
+    }
+
+    #[tokio::test]
+    async fn test_totp_enrollment() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let Ok(ServiceResponse::String(id)) = bus
+                .handle(register_account_cmd("enroll@mail.com", "Enroll"))
+                .await
+            else {
+                panic!("Account must be registered!")
+            };
+
+            '_test_code: {
+                let enrollment: TotpEnrollment = bus
+                    .handle(EnableTotp {
+                        account_id: id.clone(),
+                    })
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                assert_eq!(enrollment.recovery_codes.len(), 10);
+                assert!(enrollment
+                    .provisioning_uri
+                    .contains(&format!("secret={}", enrollment.secret)));
+
+                // * Secret is kept encrypted, and recovery codes hashed.
+                let (context_manager, _) = ContextManager::new().await;
+                let executor = context_manager.read().await.executor();
+                let auth_aggregate = account_repository_helper(executor.clone())
+                    .get(&id)
+                    .await
+                    .unwrap();
+                let totp_credential = auth_aggregate.totp.unwrap();
+                assert!(!totp_credential.confirmed);
+                assert!(!totp_credential
+                    .encrypted_secret
+                    .contains(&enrollment.secret));
+                assert!(totp_credential
+                    .recovery_code_hashes
+                    .iter()
+                    .all(|hash| !enrollment.recovery_codes.contains(hash)));
+
+                // * Not required on login until confirmed.
+                let _: IssuedToken = bus
+                    .handle(login_cmd("enroll@mail.com", "testpass"))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+
+                let totp = Totp::from_encoded_secret(&enrollment.secret).unwrap();
+                let Err(ApplicationError::InvalidCredentials) = bus
+                    .handle(ConfirmTotp {
+                        account_id: id.clone(),
+                        code: "000000".into(),
+                    })
+                    .await
+                else {
+                    panic!("Wrong code must be rejected!")
+                };
+                bus.handle(ConfirmTotp {
+                    account_id: id.clone(),
+                    code: totp.generate(Utc::now()),
+                })
+                .await
+                .unwrap();
+
+                let Err(ApplicationError::EntityAlreadyExists(_)) = bus
+                    .handle(EnableTotp {
+                        account_id: id.clone(),
+                    })
+                    .await
+                else {
+                    panic!("TOTP confirmed must not be replaced!")
+                };
+
+                let Err(ApplicationError::InvalidCredentials) = bus
+                    .handle(DisableTotp {
+                        account_id: id.clone(),
+                        code: "000000".into(),
+                    })
+                    .await
+                else {
+                    panic!("TOTP must not be disabled without second factor!")
+                };
+                bus.handle(DisableTotp {
+                    account_id: id.clone(),
+                    code: enrollment.recovery_codes[0].clone(),
+                })
+                .await
+                .unwrap();
+                let auth_aggregate = account_repository_helper(executor).get(&id).await.unwrap();
+                assert!(auth_aggregate.totp.is_none());
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_login_with_second_factor() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (_id, totp, recovery_codes) = register_with_totp(&bus, "mfa@mail.com", "Mfa").await;
+
+            '_test_code: {
+                let res = bus
+                    .handle(login_cmd("mfa@mail.com", "testpass"))
+                    .await
+                    .unwrap();
+                assert!(IssuedToken::try_from(res.clone()).is_err());
+                let challenge: MfaChallenge = res.try_into().unwrap();
+
+                // * Code accepted on confirmation is not accepted again.
+                let Err(ApplicationError::InvalidCredentials) = bus
+                    .handle(verify_mfa_cmd(&challenge, &totp.generate(Utc::now())))
+                    .await
+                else {
+                    panic!("Code used must be rejected!")
+                };
+
+                let next_code = totp.generate(Utc::now() + Duration::seconds(30));
+                let issued: IssuedToken = bus
+                    .handle(verify_mfa_cmd(&challenge, &next_code))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+
+                // * Access token is no MFA token, nor the other way around.
+                let Err(ApplicationError::InvalidToken) = bus
+                    .handle(VerifyMfa {
+                        mfa_token: issued.access_token.clone(),
+                        code: recovery_codes[0].clone(),
+                        ip: None,
+                        user_agent: None,
+                    })
+                    .await
+                else {
+                    panic!("Access token must not pass as MFA token!")
+                };
+                assert!(library::adapters::jwt::AccessToken::verify(&challenge.mfa_token).is_err());
+
+                // * Recovery code is single-use.
+                let _: IssuedToken = bus
+                    .handle(verify_mfa_cmd(&challenge, &recovery_codes[0]))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                let Err(ApplicationError::InvalidCredentials) = bus
+                    .handle(verify_mfa_cmd(&challenge, &recovery_codes[0]))
+                    .await
+                else {
+                    panic!("Recovery code used must be rejected!")
+                };
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_wrong_codes_lock_account() {
+        run_test(async {
+            let bus = Boostrap::message_bus().await;
+            let (_id, _totp, recovery_codes) =
+                register_with_totp(&bus, "mfa-lock@mail.com", "MfaLock").await;
+
+            '_test_code: {
+                let challenge: MfaChallenge = bus
+                    .handle(login_cmd("mfa-lock@mail.com", "testpass"))
+                    .await
+                    .unwrap()
+                    .try_into()
+                    .unwrap();
+                for _ in 0..5 {
+                    let Err(ApplicationError::InvalidCredentials) =
+                        bus.handle(verify_mfa_cmd(&challenge, "000000")).await
+                    else {
+                        panic!("Wrong code must be rejected!")
+                    };
+                }
+                let Err(ApplicationError::AccountLocked) = bus
+                    .handle(verify_mfa_cmd(&challenge, &recovery_codes[0]))
+                    .await
+                else {
+                    panic!("Locked account must not pass second factor!")
+                };
+            }
+        })
+        .await;
+    }
+}
@@ -0,0 +1,627 @@
+mod helpers;
+
+#[cfg(test)]
+mod unit_of_work_tests {
+    use crate::helpers::functions::*;
+
+    use library::adapters::database::{
+        BackpressurePolicy, ContextManager, EventChannelConfig, IsolationLevel, TransactionOptions,
+    };
+    use library::adapters::outbox::Outbox;
+    use library::adapters::repositories::{Repositories, Repository};
+    use library::domain::board::commands::CreateBoard;
+    use library::domain::board::entity::BoardState;
+    use library::domain::board::BoardAggregate;
+    use library::domain::builder::{Buildable, Builder};
+    use library::domain::commands::ServiceResponse;
+    use library::services::handlers::ServiceHandler;
+    use library::services::unit_of_work::UnitOfWork;
+    use library::utils::ApplicationError;
+
+    use std::sync::Arc;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    // * Each call raises `BoardCreated` which is internally notifiable.
+    fn board_with_two_internal_events() -> BoardAggregate {
+        let mut board_aggregate = BoardAggregate::builder().build();
+        for _ in 0..2 {
+            board_aggregate.create_board(CreateBoard {
+                author: Uuid::new_v4(),
+                title: "Title!".into(),
+                content: "Content".into(),
+                state: BoardState::Published,
+            });
+        }
+        board_aggregate
+    }
+
+    fn board_with_internal_event() -> BoardAggregate {
+        let mut board_aggregate = BoardAggregate::builder().build();
+        board_aggregate.create_board(CreateBoard {
+            author: Uuid::new_v4(),
+            title: "Title!".into(),
+            content: "Content".into(),
+            state: BoardState::Published,
+        });
+        board_aggregate
+    }
+
+    #[tokio::test]
+    async fn test_event_channel_overflow_with_error_policy() {
+        run_test(async {
+            let (context_manager, _receiver) =
+                ContextManager::with_channel_config(EventChannelConfig {
+                    capacity: 1,
+                    policy: BackpressurePolicy::Error,
+                })
+                .await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_with_two_internal_events();
+                uow.repository().add(&mut board_aggregate).await.unwrap();
+
+                let Err(ApplicationError::EventChannelFull) = uow.commit().await else {
+                    panic!("Overflow must be reported as EventChannelFull!")
+                };
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_event_channel_overflow_with_spill_policy() {
+        run_test(async {
+            let (context_manager, mut receiver) =
+                ContextManager::with_channel_config(EventChannelConfig {
+                    capacity: 1,
+                    policy: BackpressurePolicy::Spill,
+                })
+                .await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_with_two_internal_events();
+                uow.repository().add(&mut board_aggregate).await.unwrap();
+                uow.commit().await.unwrap();
+
+                let mut count = 0;
+                while receiver.try_recv().is_ok() {
+                    count += 1;
+                }
+                assert_eq!(count, 1);
+
+                // * Both events are externally notifiable, so spilled one is not duplicated.
+                let outboxes = Outbox::get(context_manager.read().await.executor())
+                    .await
+                    .unwrap();
+                assert_eq!(outboxes.len(), 2);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_event_channel_overflow_with_block_policy() {
+        run_test(async {
+            let (context_manager, mut receiver) =
+                ContextManager::with_channel_config(EventChannelConfig {
+                    capacity: 1,
+                    policy: BackpressurePolicy::Block,
+                })
+                .await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_with_two_internal_events();
+                uow.repository().add(&mut board_aggregate).await.unwrap();
+
+                // * Commit waits for room as long as the channel is being drained, as the bus does.
+                let draining = async {
+                    let mut count = 0;
+                    while count < 2 {
+                        receiver.recv().await.unwrap();
+                        count += 1;
+                    }
+                    count
+                };
+                let (committed, count) = tokio::time::timeout(
+                    Duration::from_secs(5),
+                    futures::future::join(uow.commit(), draining),
+                )
+                .await
+                .expect("Commit must not wait forever while the channel is drained!");
+                committed.unwrap();
+                assert_eq!(count, 2);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_outbox_is_rolled_back_with_unit_of_work() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_with_internal_event();
+                uow.repository().add(&mut board_aggregate).await.unwrap();
+                uow._commit_hook().await.unwrap();
+                uow.rollback().await.unwrap();
+
+                // * Outbox left on commit hook goes away along with the rest of the transaction.
+                let executor = context_manager.read().await.executor();
+                assert!(Outbox::get(executor).await.unwrap().is_empty());
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_transaction_options_are_applied() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let uow = UnitOfWork::<Repository<BoardAggregate>>::with_options(
+                    context_manager.clone(),
+                    TransactionOptions {
+                        isolation_level: Some(IsolationLevel::Serializable),
+                        read_only: true,
+                        deferrable: true,
+                        statement_timeout: Some(Duration::from_millis(1500)),
+                    },
+                )
+                .await
+                .unwrap();
+
+                let executor = uow.executor();
+                let (isolation, read_only, deferrable, timeout): (String, String, String, String) =
+                    sqlx::query_as(
+                        "SELECT current_setting('transaction_isolation'),
+                        current_setting('transaction_read_only'),
+                        current_setting('transaction_deferrable'),
+                        current_setting('statement_timeout')",
+                    )
+                    .fetch_one(executor.write().await.transaction().await.unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(isolation, "serializable");
+                assert_eq!(read_only, "on");
+                assert_eq!(deferrable, "on");
+                assert_eq!(timeout, "1500ms");
+                uow.rollback().await.unwrap();
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_unit_of_work_with_options_joins_ongoing_transaction() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                uow.repository().add(&mut board_aggregate).await.unwrap();
+
+                // * Options are not applied to the transaction already in progress.
+                let joined = UnitOfWork::<Repository<BoardAggregate>>::with_options(
+                    context_manager.clone(),
+                    TransactionOptions::read_only(),
+                )
+                .await
+                .unwrap();
+                let executor = joined.executor();
+                let (read_only, count): (String, i64) = sqlx::query_as(
+                    "SELECT current_setting('transaction_read_only'), COUNT(*) FROM community_board",
+                )
+                .fetch_one(executor.write().await.transaction().await.unwrap())
+                .await
+                .unwrap();
+                assert_eq!(read_only, "off");
+                assert_eq!(count, 1);
+                joined.rollback().await.unwrap();
+                uow.rollback().await.unwrap();
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_read_only_transaction_rejects_write() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+            context_manager.write().await.transaction_options = TransactionOptions::read_only();
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut board_aggregate = board_create_helper(BoardState::Published);
+                let Err(ApplicationError::DatabaseConnectionError(_)) =
+                    uow.repository().add(&mut board_aggregate).await
+                else {
+                    panic!("Write in read only transaction must fail!")
+                };
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_statement_timeout_defaults_to_config() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+
+                let executor = uow.executor();
+                let (timeout,): (String,) =
+                    sqlx::query_as("SELECT current_setting('statement_timeout')")
+                        .fetch_one(executor.write().await.transaction().await.unwrap())
+                        .await
+                        .unwrap();
+                assert_eq!(timeout, "30s");
+                uow.rollback().await.unwrap();
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_statement_timeout() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let uow = UnitOfWork::<Repository<BoardAggregate>>::with_options(
+                    context_manager.clone(),
+                    TransactionOptions {
+                        statement_timeout: Some(Duration::from_millis(50)),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+
+                let executor = uow.executor();
+                let res = sqlx::query("SELECT pg_sleep(1)")
+                    .execute(executor.write().await.transaction().await.unwrap())
+                    .await;
+                assert!(res.is_err());
+                uow.rollback().await.unwrap();
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_nested_unit_of_work_rollback() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut outer_board = board_with_internal_event();
+                let outer_id = uow.repository().add(&mut outer_board).await.unwrap();
+
+                let mut nested_uow = uow.nested::<Repository<BoardAggregate>>().await.unwrap();
+                let mut inner_board = board_with_internal_event();
+                let inner_id = nested_uow.repository().add(&mut inner_board).await.unwrap();
+                nested_uow.rollback().await.unwrap();
+
+                // * Outer transaction is still usable after inner scope is rolled back.
+                let executor = uow.executor();
+                let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM community_board")
+                    .fetch_one(executor.write().await.transaction().await.unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(count, 1);
+                uow.commit().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                assert!(uow.repository().get(&outer_id).await.is_ok());
+                assert!(uow.repository().get(&inner_id).await.is_err());
+
+                // * Event collected in rolled back scope is discarded.
+                let mut count = 0;
+                while receiver.try_recv().is_ok() {
+                    count += 1;
+                }
+                assert_eq!(count, 1);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_nested_unit_of_work_commit() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut outer_board = board_with_internal_event();
+                uow.repository().add(&mut outer_board).await.unwrap();
+
+                let mut nested_uow = uow.nested::<Repository<BoardAggregate>>().await.unwrap();
+                let mut inner_board = board_with_internal_event();
+                let inner_id = nested_uow.repository().add(&mut inner_board).await.unwrap();
+                nested_uow.commit().await.unwrap();
+
+                // * Nothing is notified until the outermost unit of work commits.
+                assert!(receiver.try_recv().is_err());
+                uow.commit().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                assert!(uow.repository().get(&inner_id).await.is_ok());
+
+                let mut count = 0;
+                while receiver.try_recv().is_ok() {
+                    count += 1;
+                }
+                assert_eq!(count, 2);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_nested_unit_of_work_is_undone_by_outer_rollback() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+
+                let mut nested_uow = uow.nested::<Repository<BoardAggregate>>().await.unwrap();
+                let mut inner_board = board_with_internal_event();
+                let inner_id = nested_uow.repository().add(&mut inner_board).await.unwrap();
+                nested_uow.commit().await.unwrap();
+                uow.rollback().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                assert!(uow.repository().get(&inner_id).await.is_err());
+                assert!(receiver.try_recv().is_err());
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_handler_invoked_within_another_joins_its_transaction() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+            let create_board = || CreateBoard {
+                author: Uuid::new_v4(),
+                title: "Title!".into(),
+                content: "Content".into(),
+                state: BoardState::Published,
+            };
+
+            '_test_case: {
+                let uow = UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+
+                // * Unit of work begun by the handler commits only to the savepoint.
+                let Ok(ServiceResponse::String(inner_id)) =
+                    ServiceHandler::create_board(create_board(), context_manager.clone()).await
+                else {
+                    panic!("Board must be created!")
+                };
+                assert!(receiver.try_recv().is_err());
+                uow.rollback().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                assert!(uow.repository().get(&inner_id).await.is_err());
+                uow.rollback().await.unwrap();
+                assert!(receiver.try_recv().is_err());
+            }
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut outer_board = board_with_internal_event();
+                uow.repository().add(&mut outer_board).await.unwrap();
+
+                let Ok(ServiceResponse::String(inner_id)) =
+                    ServiceHandler::create_board(create_board(), context_manager.clone()).await
+                else {
+                    panic!("Board must be created!")
+                };
+                assert!(receiver.try_recv().is_err());
+                uow.commit().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                assert!(uow.repository().get(&inner_id).await.is_ok());
+
+                let mut count = 0;
+                while receiver.try_recv().is_ok() {
+                    count += 1;
+                }
+                assert_eq!(count, 2);
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_unit_of_work_spanning_repositories() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow = UnitOfWork::<Repositories>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+
+                // * Every repository works on the same transaction.
+                let executor = uow.executor();
+                assert!(Arc::ptr_eq(&uow.boards().executor, &executor));
+                assert!(Arc::ptr_eq(&uow.accounts().executor, &executor));
+
+                let mut board_aggregate = board_with_internal_event();
+                let id = uow.boards().add(&mut board_aggregate).await.unwrap();
+                uow.commit().await.unwrap();
+
+                let mut uow = UnitOfWork::<Repositories>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+                assert!(uow.boards().get(&id).await.is_ok());
+                assert!(receiver.try_recv().is_ok());
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_unit_of_work_spanning_repositories_rolls_back_together() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow = UnitOfWork::<Repositories>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+
+                let mut board_aggregate = board_with_internal_event();
+                let board_id = uow.boards().add(&mut board_aggregate).await.unwrap();
+                let mut auth_aggregate = account_create_helper();
+                let account_id = uow.accounts().add(&mut auth_aggregate).await.unwrap();
+                uow.rollback().await.unwrap();
+
+                // * Neither change is left, and no event is notified.
+                let mut uow = UnitOfWork::<Repositories>::new(context_manager.clone())
+                    .await
+                    .unwrap();
+                assert!(uow.boards().get(&board_id).await.is_err());
+                assert!(uow.accounts().get(&account_id).await.is_err());
+                uow.rollback().await.unwrap();
+                assert!(receiver.try_recv().is_err());
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_dropped_unit_of_work_is_rolled_back() {
+        run_test(async {
+            let (context_manager, _receiver) = ContextManager::new().await;
+            let id: String;
+            let executor;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                executor = uow.executor();
+                let mut board_aggregate = board_with_internal_event();
+                id = uow.repository().add(&mut board_aggregate).await.unwrap();
+            }
+
+            assert!(executor.read().await.transaction_scope().is_none());
+            let mut uow = UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                .await
+                .unwrap();
+            assert!(uow.repository().get(&id).await.is_err());
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_dropped_nested_unit_of_work_is_rolled_back() {
+        run_test(async {
+            let (context_manager, mut receiver) = ContextManager::new().await;
+
+            '_test_case: {
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                let mut outer_board = board_with_internal_event();
+                let outer_id = uow.repository().add(&mut outer_board).await.unwrap();
+
+                let inner_id = '_nested_scope: {
+                    let mut nested_uow = uow.nested::<Repository<BoardAggregate>>().await.unwrap();
+                    let mut inner_board = board_with_internal_event();
+                    nested_uow.repository().add(&mut inner_board).await.unwrap()
+                };
+
+                // * Outer unit of work carries on, without what has been done within the abandoned scope.
+                let executor = uow.executor();
+                let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM community_board")
+                    .fetch_one(executor.write().await.transaction().await.unwrap())
+                    .await
+                    .unwrap();
+                assert_eq!(count, 1);
+                uow.commit().await.unwrap();
+
+                let mut uow =
+                    UnitOfWork::<Repository<BoardAggregate>>::new(context_manager.clone())
+                        .await
+                        .unwrap();
+                assert!(uow.repository().get(&outer_id).await.is_ok());
+                assert!(uow.repository().get(&inner_id).await.is_err());
+                uow.rollback().await.unwrap();
+
+                // * Events collected within the abandoned scope are discarded.
+                let mut received = 0;
+                while receiver.try_recv().is_ok() {
+                    received += 1;
+                }
+                assert_eq!(received, 1);
+            }
+        })
+        .await
+    }
+}